@@ -4,29 +4,69 @@ use actix_cors::Cors;
 use actix_files::Files;
 use actix_web::{web, App, HttpResponse, HttpServer};
 use chrono::{Local, NaiveDate};
-use regex::Regex;
+use futures_util::StreamExt;
 use serde::Deserialize;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::info;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{info, warn};
 
-use crate::db::Db;
+use crate::bus::{SharedCurrent, WatchEvent};
+use crate::daterange::{self, TimeRange};
+use crate::db::{Db, Rule};
+use crate::repository::Repository;
+
+/// Handle to whichever backend is serving the core event-metrics path (local SQLite by
+/// default, or Postgres when `ATRACKER_DATABASE_URL` is set).
+type Repo = Arc<dyn Repository + Send + Sync>;
 
 #[derive(Deserialize)]
 pub struct DateQuery {
+    /// Legacy single-date param, still honored for backward compatibility.
     date: Option<String>,
+    /// Human-friendly range, e.g. `today`, `yesterday`, `last 7 days`, `this week`.
+    range: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct DaysQuery {
     days: Option<i32>,
+    range: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
 }
 
-fn parse_date(date_str: &Option<String>) -> NaiveDate {
+/// Parse a `?date=` param, defaulting to today if absent — but, unlike the legacy
+/// helper this replaced, returning an error instead of silently falling back to today
+/// when it's present and malformed.
+fn parse_date_strict(date_str: &Option<String>) -> Result<NaiveDate, String> {
     match date_str {
-        Some(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap_or_else(|_| Local::now().date_naive()),
-        None => Local::now().date_naive(),
+        Some(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("invalid date '{s}': {e}")),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+/// Resolve a `DateQuery` into a half-open [`TimeRange`]. The legacy `date` param (if
+/// present and `range`/`from`/`to` are absent) takes precedence as a single day for
+/// backward compatibility; otherwise fall through to the range parser, which also
+/// accepts an explicit `YYYY-MM-DD..YYYY-MM-DD` window.
+fn resolve_range(query: &DateQuery) -> Result<TimeRange, String> {
+    if let Some(date) = &query.date {
+        if query.range.is_none() && query.from.is_none() && query.to.is_none() {
+            let d = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|e| format!("invalid date '{date}': {e}"))?;
+            return Ok(TimeRange::single_day(d));
+        }
     }
+    daterange::parse_range(query.range.as_deref(), query.from.as_deref(), query.to.as_deref())
+}
+
+fn bad_range(err: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({ "error": err }))
 }
 
 fn format_duration(secs: f64) -> String {
@@ -39,18 +79,6 @@ fn format_duration(secs: f64) -> String {
     }
 }
 
-fn match_category_color(wm_class: &str, categories: &[crate::db::Category]) -> String {
-    let wm_lower = wm_class.to_lowercase();
-    for cat in categories {
-        if let Ok(re) = Regex::new(&format!("(?i){}", cat.wm_class_pattern)) {
-            if re.is_match(&wm_lower) {
-                return cat.color.clone();
-            }
-        }
-    }
-    "#64748b".to_string() // Default slate color
-}
-
 /// GET /api/status
 async fn status(_db: web::Data<Arc<Db>>) -> HttpResponse {
     let db_path = crate::db::db_path();
@@ -61,81 +89,123 @@ async fn status(_db: web::Data<Arc<Db>>) -> HttpResponse {
     }))
 }
 
-/// GET /api/events?date=YYYY-MM-DD
-async fn events(db: web::Data<Arc<Db>>, query: web::Query<DateQuery>) -> HttpResponse {
-    let d = parse_date(&query.date);
-    let db = db.clone();
-    let result = tokio::task::spawn_blocking(move || db.get_events(d)).await;
+/// GET /api/events?date=YYYY-MM-DD or ?range=last+7+days or ?from=..&to=..
+async fn events(repo: web::Data<Repo>, query: web::Query<DateQuery>) -> HttpResponse {
+    let range = match resolve_range(&query) {
+        Ok(range) => range,
+        Err(e) => return bad_range(e),
+    };
+    let repo = repo.get_ref().clone();
+    let result = tokio::task::spawn_blocking(move || repo.get_events(range)).await;
     match result {
         Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
-            "date": d.to_string(),
+            "start": range.start_day().to_string(),
+            "end": range.end_day().to_string(),
             "events": rows,
         })),
         _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
     }
 }
 
-/// GET /api/summary?date=YYYY-MM-DD
-async fn summary(db: web::Data<Arc<Db>>, query: web::Query<DateQuery>) -> HttpResponse {
-    let d = parse_date(&query.date);
-    let db = db.clone();
+/// GET /api/summary?date=YYYY-MM-DD or ?range=last+7+days or ?from=..&to=..
+async fn summary(repo: web::Data<Repo>, query: web::Query<DateQuery>) -> HttpResponse {
+    let range = match resolve_range(&query) {
+        Ok(range) => range,
+        Err(e) => return bad_range(e),
+    };
+    let repo = repo.get_ref().clone();
     let result = tokio::task::spawn_blocking(move || {
-        let mut rows = db.get_summary(d)?;
-        let categories = db.get_categories()?;
+        let mut rows = repo.get_summary(range)?;
         for row in &mut rows {
-            row.color = Some(match_category_color(&row.wm_class, &categories));
             row.total_formatted = Some(format_duration(row.total_secs));
         }
-        Ok::<_, rusqlite::Error>(rows)
+        Ok::<_, anyhow::Error>(rows)
     })
     .await;
     match result {
         Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
-            "date": d.to_string(),
+            "start": range.start_day().to_string(),
+            "end": range.end_day().to_string(),
             "summary": rows,
         })),
         _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
     }
 }
 
-/// GET /api/timeline?date=YYYY-MM-DD
-async fn timeline(db: web::Data<Arc<Db>>, query: web::Query<DateQuery>) -> HttpResponse {
-    let d = parse_date(&query.date);
+/// GET /api/timeline?date=YYYY-MM-DD or ?range=last+7+days or ?from=..&to=..
+async fn timeline(db: web::Data<Arc<Db>>, repo: web::Data<Repo>, query: web::Query<DateQuery>) -> HttpResponse {
+    let range = match resolve_range(&query) {
+        Ok(range) => range,
+        Err(e) => return bad_range(e),
+    };
     let db = db.clone();
+    let repo = repo.get_ref().clone();
     let result = tokio::task::spawn_blocking(move || {
-        let mut rows = db.get_timeline(d)?;
-        let categories = db.get_categories()?;
-        for row in &mut rows {
-            row.color = Some(match_category_color(&row.wm_class, &categories));
-        }
-        Ok::<_, rusqlite::Error>(rows)
+        let rows = repo.get_timeline(range)?;
+        // Annotations are a local-first, SQLite-only feature — always read from `db`,
+        // never from the pluggable event-metrics repo.
+        let annotations = db.get_annotations(range)?;
+        Ok::<_, anyhow::Error>((rows, annotations))
     })
     .await;
     match result {
-        Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
-            "date": d.to_string(),
+        Ok(Ok((rows, annotations))) => HttpResponse::Ok().json(serde_json::json!({
+            "start": range.start_day().to_string(),
+            "end": range.end_day().to_string(),
             "timeline": rows,
+            "annotations": annotations,
         })),
         _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
     }
 }
 
-/// GET /api/history?days=7
-async fn history(db: web::Data<Arc<Db>>, query: web::Query<DaysQuery>) -> HttpResponse {
-    let days = query.days.unwrap_or(7);
+/// GET /api/annotations?date=YYYY-MM-DD or ?range=last+7+days or ?from=..&to=..
+async fn annotations(db: web::Data<Arc<Db>>, query: web::Query<DateQuery>) -> HttpResponse {
+    let range = match resolve_range(&query) {
+        Ok(range) => range,
+        Err(e) => return bad_range(e),
+    };
     let db = db.clone();
+    let result = tokio::task::spawn_blocking(move || db.get_annotations(range)).await;
+    match result {
+        Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
+            "start": range.start_day().to_string(),
+            "end": range.end_day().to_string(),
+            "annotations": rows,
+        })),
+        _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
+    }
+}
+
+/// GET /api/history?days=7 or ?range=last+7+days or ?from=..&to=..
+async fn history(repo: web::Data<Repo>, query: web::Query<DaysQuery>) -> HttpResponse {
+    // `days=N` is just sugar for "last N days ending today" — reuse the same range
+    // parser rather than re-deriving the date math, so this endpoint answers the exact
+    // span requested instead of collapsing it to a count and re-measuring from today
+    // (which silently returned the wrong window for any range not ending today).
+    let range = if query.range.is_some() || query.from.is_some() || query.to.is_some() {
+        daterange::parse_range(query.range.as_deref(), query.from.as_deref(), query.to.as_deref())
+    } else {
+        daterange::parse_time_range(&format!("last {}d", query.days.unwrap_or(7)))
+    };
+    let range = match range {
+        Ok(range) => range,
+        Err(e) => return bad_range(e),
+    };
+    let repo = repo.get_ref().clone();
     let result = tokio::task::spawn_blocking(move || {
-        let mut rows = db.get_daily_totals(days)?;
+        let mut rows = repo.get_daily_totals(range)?;
         for row in &mut rows {
             row.active_formatted = Some(format_duration(row.active_secs));
             row.idle_formatted = Some(format_duration(row.idle_secs));
         }
-        Ok::<_, rusqlite::Error>(rows)
+        Ok::<_, anyhow::Error>(rows)
     })
     .await;
     match result {
         Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
-            "days": days,
+            "start": range.start_day().to_string(),
+            "end": range.end_day().to_string(),
             "history": rows,
         })),
         _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
@@ -143,19 +213,186 @@ async fn history(db: web::Data<Arc<Db>>, query: web::Query<DaysQuery>) -> HttpRe
 }
 
 /// GET /api/categories
-async fn categories(db: web::Data<Arc<Db>>) -> HttpResponse {
+async fn categories(repo: web::Data<Repo>) -> HttpResponse {
+    let repo = repo.get_ref().clone();
+    let result = tokio::task::spawn_blocking(move || repo.get_categories()).await;
+    match result {
+        Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
+            "categories": rows,
+        })),
+        _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
+    }
+}
+
+/// GET /api/connections?date=YYYY-MM-DD — remote endpoints observed per app.
+async fn connections(db: web::Data<Arc<Db>>, query: web::Query<DateQuery>) -> HttpResponse {
+    let d = match parse_date_strict(&query.date) {
+        Ok(d) => d,
+        Err(e) => return bad_range(e),
+    };
     let db = db.clone();
-    let result = tokio::task::spawn_blocking(move || db.get_categories()).await;
+    let result = tokio::task::spawn_blocking(move || db.get_connections(d)).await;
     match result {
         Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
+            "date": d.to_string(),
+            "connections": rows.into_iter().map(|(app, endpoints)| {
+                serde_json::json!({ "wm_class": app, "endpoints": endpoints })
+            }).collect::<Vec<_>>(),
+        })),
+        _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
+    }
+}
+
+/// GET /api/category_totals?date=YYYY-MM-DD — time-per-category rollup for one day.
+async fn category_totals(repo: web::Data<Repo>, query: web::Query<DateQuery>) -> HttpResponse {
+    let d = match parse_date_strict(&query.date) {
+        Ok(d) => d,
+        Err(e) => return bad_range(e),
+    };
+    let repo = repo.get_ref().clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut rows = repo.get_category_totals(d)?;
+        for row in &mut rows {
+            row.total_formatted = Some(format_duration(row.total_secs));
+        }
+        Ok::<_, anyhow::Error>(rows)
+    })
+    .await;
+    match result {
+        Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({
+            "date": d.to_string(),
             "categories": rows,
         })),
         _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
     }
 }
 
+/// GET /api/rules
+async fn list_rules(db: web::Data<Arc<Db>>) -> HttpResponse {
+    let db = db.clone();
+    let result = tokio::task::spawn_blocking(move || db.get_rules()).await;
+    match result {
+        Ok(Ok(rows)) => HttpResponse::Ok().json(serde_json::json!({ "rules": rows })),
+        _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
+    }
+}
+
+/// POST /api/rules
+async fn create_rule(db: web::Data<Arc<Db>>, rule: web::Json<Rule>) -> HttpResponse {
+    let db = db.clone();
+    let rule = rule.into_inner();
+    let result = tokio::task::spawn_blocking(move || db.insert_rule(&rule)).await;
+    match result {
+        Ok(Ok(id)) => HttpResponse::Ok().json(serde_json::json!({ "id": id })),
+        _ => HttpResponse::InternalServerError().json(serde_json::json!({"error": "db error"})),
+    }
+}
+
+/// Escape a label value per the Prometheus text exposition format.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// GET /api/metrics — Prometheus text exposition of today's activity totals.
+async fn metrics(repo: web::Data<Repo>, current: web::Data<SharedCurrent>) -> HttpResponse {
+    let repo = repo.get_ref().clone();
+    let today = Local::now().date_naive();
+    let result = tokio::task::spawn_blocking(move || {
+        let totals = repo.get_category_totals(today)?;
+        let daily = repo.get_daily_totals(TimeRange::single_day(today))?;
+        Ok::<_, anyhow::Error>((totals, daily))
+    })
+    .await;
+
+    let (totals, daily) = match result {
+        Ok(Ok(data)) => data,
+        _ => return HttpResponse::InternalServerError().body("# db error\n"),
+    };
+
+    let mut body = String::new();
+
+    // Gauges, not counters: both reset to zero at local midnight rather than climbing
+    // monotonically, so a Prometheus counter's rate()/increase() would read that reset
+    // as a (nonsensical) negative delta.
+    body.push_str("# HELP atracker_active_seconds Total active seconds today, by category.\n");
+    body.push_str("# TYPE atracker_active_seconds gauge\n");
+    for row in &totals {
+        body.push_str(&format!(
+            "atracker_active_seconds{{category=\"{}\"}} {}\n",
+            escape_label(&row.category),
+            row.total_secs
+        ));
+    }
+
+    let idle_secs = daily
+        .iter()
+        .find(|d| d.day == today.to_string())
+        .map(|d| d.idle_secs)
+        .unwrap_or(0.0);
+    body.push_str("# HELP atracker_idle_seconds Total idle seconds today.\n");
+    body.push_str("# TYPE atracker_idle_seconds gauge\n");
+    body.push_str(&format!("atracker_idle_seconds {idle_secs}\n"));
+
+    body.push_str("# HELP atracker_current_window Currently focused window (1 while active).\n");
+    body.push_str("# TYPE atracker_current_window gauge\n");
+    if let Ok(slot) = current.read() {
+        if let Some(event) = slot.as_ref() {
+            body.push_str(&format!(
+                "atracker_current_window{{wm_class=\"{}\",title=\"{}\"}} 1\n",
+                escape_label(&event.wm_class),
+                escape_label(&event.title)
+            ));
+        }
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body)
+}
+
+/// GET /api/stream — Server-Sent Events feed of live watcher updates.
+async fn stream(bus: web::Data<broadcast::Sender<WatchEvent>>) -> HttpResponse {
+    let rx = bus.subscribe();
+    let body = BroadcastStream::new(rx)
+        .take_while(|item| {
+            let keep = match item {
+                Err(BroadcastStreamRecvError::Lagged(n)) => {
+                    warn!("SSE client lagged by {n} events, dropping it");
+                    false
+                }
+                Ok(_) => true,
+            };
+            futures_util::future::ready(keep)
+        })
+        .filter_map(|item| async move {
+            match item {
+                Ok(event) => match serde_json::to_string(&event) {
+                    Ok(json) => Some(Ok::<_, std::io::Error>(web::Bytes::from(format!(
+                        "data: {json}\n\n"
+                    )))),
+                    Err(e) => {
+                        warn!("Failed to serialize watch event: {e}");
+                        None
+                    }
+                },
+                Err(_) => None,
+            }
+        });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .append_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}
+
 /// Start the API server on the given port.
-pub async fn run_server(db: Arc<Db>, port: u16) -> std::io::Result<()> {
+pub async fn run_server(
+    db: Arc<Db>,
+    repo: Repo,
+    bus: broadcast::Sender<WatchEvent>,
+    current: SharedCurrent,
+    port: u16,
+) -> std::io::Result<()> {
     let dashboard_dir = dashboard_path();
     let has_dashboard = dashboard_dir.exists();
 
@@ -174,12 +411,22 @@ pub async fn run_server(db: Arc<Db>, port: u16) -> std::io::Result<()> {
         let mut app = App::new()
             .wrap(cors)
             .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::new(repo.clone()))
+            .app_data(web::Data::new(bus.clone()))
+            .app_data(web::Data::new(current.clone()))
             .route("/api/status", web::get().to(status))
             .route("/api/events", web::get().to(events))
             .route("/api/summary", web::get().to(summary))
             .route("/api/timeline", web::get().to(timeline))
             .route("/api/history", web::get().to(history))
-            .route("/api/categories", web::get().to(categories));
+            .route("/api/categories", web::get().to(categories))
+            .route("/api/category_totals", web::get().to(category_totals))
+            .route("/api/stream", web::get().to(stream))
+            .route("/api/metrics", web::get().to(metrics))
+            .route("/api/rules", web::get().to(list_rules))
+            .route("/api/rules", web::post().to(create_rule))
+            .route("/api/connections", web::get().to(connections))
+            .route("/api/annotations", web::get().to(annotations));
 
         if has_dashboard {
             app = app.service(
@@ -0,0 +1,49 @@
+//! Shared broadcast bus for pushing live watcher events to subscribers (e.g. SSE clients).
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+
+/// How a [`WatchEvent`] relates to the watcher's focus state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    /// The active window changed while not idle.
+    Active,
+    /// The user just went idle.
+    Idle,
+    /// The user just returned from idle.
+    Resume,
+}
+
+/// A single live update published by the [`crate::watcher::Watcher`].
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchEvent {
+    pub ts: DateTime<Local>,
+    pub wm_class: String,
+    pub title: String,
+    pub pid: i64,
+    pub kind: EventKind,
+}
+
+/// Capacity of the broadcast channel. Slow subscribers that fall this far behind are
+/// dropped (see `RecvError::Lagged` handling in the SSE handler) rather than blocking
+/// the watcher.
+const BUS_CAPACITY: usize = 256;
+
+/// Create a fresh broadcast bus. Clone the returned sender into both the watcher and
+/// the API server; call `.subscribe()` per SSE client.
+pub fn new_bus() -> broadcast::Sender<WatchEvent> {
+    let (tx, _rx) = broadcast::channel(BUS_CAPACITY);
+    tx
+}
+
+/// Snapshot of the watcher's current focus, shared with the API server so handlers
+/// (e.g. `/api/metrics`) can read it without going through the broadcast channel.
+pub type SharedCurrent = Arc<RwLock<Option<WatchEvent>>>;
+
+/// Create an empty shared current-window slot.
+pub fn new_shared_current() -> SharedCurrent {
+    Arc::new(RwLock::new(None))
+}
@@ -0,0 +1,94 @@
+//! TOML config for the watcher daemon, loaded from the XDG config dir and hot-reloaded
+//! without restarting the process — the poll interval and idle threshold take effect
+//! on the next tick after the file changes on disk.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// Default poll interval, matching the daemon's historical hardcoded value.
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+/// Default idle threshold (2 minutes), matching the daemon's historical hardcoded value.
+const DEFAULT_IDLE_THRESHOLD_MS: u64 = 120_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub poll_interval_secs: u64,
+    pub idle_threshold_ms: u64,
+    /// Preferred window backend: "mutter" (D-Bus extension) or "xdotool".
+    pub window_backend: String,
+    pub dbus_window_service: String,
+    pub dbus_idle_service: String,
+    /// Base URL of the sync server, e.g. "https://sync.example.com". Sync is opt-in and
+    /// disabled unless both this and `sync_key` are set.
+    pub sync_server_url: Option<String>,
+    /// Passphrase events are encrypted with before upload; never leaves the device.
+    pub sync_key: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: DEFAULT_POLL_INTERVAL_SECS,
+            idle_threshold_ms: DEFAULT_IDLE_THRESHOLD_MS,
+            window_backend: "mutter".to_string(),
+            dbus_window_service: "org.atracker.WindowTracker".to_string(),
+            dbus_idle_service: "org.gnome.Mutter.IdleMonitor".to_string(),
+            sync_server_url: None,
+            sync_key: None,
+        }
+    }
+}
+
+/// Get the config file path, respecting `XDG_CONFIG_HOME`.
+pub fn config_path() -> PathBuf {
+    let dir = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(d) => PathBuf::from(d),
+        Err(_) => {
+            let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+            PathBuf::from(home).join(".config")
+        }
+    };
+    dir.join("atracker").join("config.toml")
+}
+
+/// Load the config, falling back to defaults if the file is missing or invalid.
+pub fn load() -> Config {
+    load_from(&config_path())
+}
+
+fn load_from(path: &Path) -> Config {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => match toml::from_str(&contents) {
+            Ok(cfg) => {
+                info!("Loaded config from {}", path.display());
+                cfg
+            }
+            Err(e) => {
+                warn!("Failed to parse config at {}: {e} — using defaults", path.display());
+                Config::default()
+            }
+        },
+        Err(_) => Config::default(),
+    }
+}
+
+/// Get the file's last-modified time, if it exists.
+pub fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Re-read the config file if its mtime has advanced since `last_mtime`, updating it
+/// in place. Returns the freshly-loaded config on change, `None` otherwise.
+pub fn reload_if_changed(path: &Path, last_mtime: &mut Option<SystemTime>) -> Option<Config> {
+    let current = mtime(path);
+    if current.is_some() && current != *last_mtime {
+        *last_mtime = current;
+        info!("Config file changed, reloading");
+        Some(load_from(path))
+    } else {
+        None
+    }
+}
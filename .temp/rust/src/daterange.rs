@@ -0,0 +1,119 @@
+//! Human-friendly time-range parsing for the query API and CLI, in the spirit of
+//! reminder-bot's interval parser — `last 7d`, `this-week`, or an explicit
+//! `2024-01-01..2024-01-31` instead of every caller hand-formatting `NaiveDate`s.
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// A half-open `[start, end)` span, so "today" or "last 7 days" can be expressed
+/// without the final-second-of-the-day loss an inclusive `T23:59:59` upper bound has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl TimeRange {
+    /// The half-open span covering the single calendar day `date`.
+    pub fn single_day(date: NaiveDate) -> Self {
+        Self::days(date, date)
+    }
+
+    /// The half-open span covering every calendar day in the inclusive `[start, end]`.
+    pub fn days(start: NaiveDate, end: NaiveDate) -> Self {
+        let next = end.succ_opt().unwrap_or(end);
+        Self {
+            start: start.and_time(NaiveTime::MIN),
+            end: next.and_time(NaiveTime::MIN),
+        }
+    }
+
+    /// Number of whole calendar days this range spans (at least 1), for callers that
+    /// think in day counts rather than timestamps.
+    pub fn num_days(&self) -> i64 {
+        (self.end - self.start).num_days().max(1)
+    }
+
+    /// The first calendar day included in this range, for display.
+    pub fn start_day(&self) -> NaiveDate {
+        self.start.date()
+    }
+
+    /// The last calendar day included in this half-open range, for display — the day
+    /// just before the exclusive `end` bound.
+    pub fn end_day(&self) -> NaiveDate {
+        (self.end - chrono::Duration::seconds(1)).date()
+    }
+}
+
+/// Parse a `range=` value, or an explicit `from`/`to` pair of dates, into a
+/// [`TimeRange`]. Returns a human-readable error instead of silently defaulting, so
+/// callers can tell "bad input" from "empty day."
+pub fn parse_range(
+    range: Option<&str>,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<TimeRange, String> {
+    if from.is_some() || to.is_some() {
+        let from = from.ok_or("`from` is required when `to` is given")?;
+        let to = to.ok_or("`to` is required when `from` is given")?;
+        let start = parse_date(from)?;
+        let end = parse_date(to)?;
+        if end < start {
+            return Err(format!("`to` ({to}) is before `from` ({from})"));
+        }
+        return Ok(TimeRange::days(start, end));
+    }
+
+    match range.map(str::trim) {
+        None => Ok(TimeRange::single_day(Local::now().date_naive())),
+        Some(s) => parse_time_range(s),
+    }
+}
+
+/// Parse a single range expression: `today`, `yesterday`, `this week`/`this-week`,
+/// `last Nd`/`last N days`, or an explicit `YYYY-MM-DD..YYYY-MM-DD` window.
+pub fn parse_time_range(s: &str) -> Result<TimeRange, String> {
+    let s = s.trim();
+    if let Some((from, to)) = s.split_once("..") {
+        let start = parse_date(from)?;
+        let end = parse_date(to)?;
+        if end < start {
+            return Err(format!("'{to}' is before '{from}'"));
+        }
+        return Ok(TimeRange::days(start, end));
+    }
+
+    let today = Local::now().date_naive();
+    match s.to_lowercase().as_str() {
+        "today" => Ok(TimeRange::single_day(today)),
+        "yesterday" => {
+            let y = today.pred_opt().ok_or("date underflow")?;
+            Ok(TimeRange::single_day(y))
+        }
+        "this week" | "this-week" => {
+            let start = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+            Ok(TimeRange::days(start, today))
+        }
+        _ => parse_last_n_days(s)
+            .map(|(start, end)| TimeRange::days(start, end))
+            .ok_or_else(|| format!("could not parse range '{s}'")),
+    }
+}
+
+/// Parse forms like `"last 7 days"` / `"last 7d"` / `"7d"`.
+fn parse_last_n_days(s: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let rest = s.strip_prefix("last").map(str::trim).unwrap_or(s);
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() {
+        return None;
+    }
+    let n: i64 = digits.parse().ok()?;
+    let today = Local::now().date_naive();
+    let start = today - chrono::Duration::days(n.saturating_sub(1));
+    Some((start, today))
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .map_err(|e| format!("invalid date '{s}': {e}"))
+}
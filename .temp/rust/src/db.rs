@@ -1,13 +1,42 @@
 //! SQLite database layer for activity events.
 
 use chrono::NaiveDate;
+use crate::daterange::TimeRange;
+use r2d2::{CustomizeConnection, Pool};
+use r2d2_sqlite::SqliteConnectionManager;
+use regex::Regex;
 use rusqlite::{params, Connection};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
 use std::path::PathBuf;
 use std::sync::Mutex;
-use tracing::info;
+use std::time::Duration;
+use tracing::{info, warn};
 
-const SCHEMA: &str = r#"
+/// Busy-timeout applied to every pooled (and the writer) connection, so a reader
+/// waiting on the writer's lock retries instead of immediately erroring out.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Applies the pragmas this daemon needs for concurrent access on every connection
+/// the pool hands out.
+#[derive(Debug)]
+struct ConnectionCustomizer;
+
+impl CustomizeConnection<Connection, rusqlite::Error> for ConnectionCustomizer {
+    fn on_acquire(&self, conn: &mut Connection) -> Result<(), rusqlite::Error> {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        Ok(())
+    }
+}
+
+/// The schema exactly as created by the original (pre-migrations) binary: `events`
+/// without `connections`, and no `annotations`/`rules` tables. This is what
+/// [`crate::migrations::migration_001_baseline`] replays for a brand-new database, and
+/// what a database stamped at `user_version=0` is assumed to already have — every table
+/// and column added since lives in its own later migration instead of here.
+pub(crate) const BASELINE_SCHEMA: &str = r#"
 CREATE TABLE IF NOT EXISTS events (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     timestamp TEXT NOT NULL,
@@ -30,7 +59,13 @@ CREATE INDEX IF NOT EXISTS idx_events_timestamp ON events(timestamp);
 CREATE INDEX IF NOT EXISTS idx_events_wm_class ON events(wm_class);
 "#;
 
-const DEFAULT_CATEGORIES: &[(&str, &str, &str)] = &[
+/// Color assigned to events whose `wm_class` doesn't match any configured category.
+pub(crate) const UNCATEGORIZED_COLOR: &str = "#64748b";
+
+/// Seeded into `categories` on first connect by both backends (see
+/// [`crate::postgres_repo::PgRepository::connect`]) so classification works out of the
+/// box regardless of which one is in use.
+pub(crate) const DEFAULT_CATEGORIES: &[(&str, &str, &str)] = &[
     ("Browser", "firefox|chromium|google-chrome|brave|zen", "#3b82f6"),
     ("Terminal", "gnome-terminal|kitty|alacritty|wezterm|foot|Tilix|konsole", "#10b981"),
     ("Editor", "code|Code|cursor|Cursor|neovim|emacs|sublime|jetbrains", "#8b5cf6"),
@@ -40,12 +75,16 @@ const DEFAULT_CATEGORIES: &[(&str, &str, &str)] = &[
     ("Office", "libreoffice|soffice|evince|okular", "#14b8a6"),
 ];
 
-/// Shared database handle.
+/// Shared database handle. Reads are served from a pool of WAL-mode connections so the
+/// API server can serve the dashboard concurrently with the watcher's writes; writes go
+/// through a single dedicated connection to keep `INSERT`s serialized without contending
+/// with readers for SQLite's single writer lock.
 pub struct Db {
-    conn: Mutex<Connection>,
+    read_pool: Pool<SqliteConnectionManager>,
+    writer: Mutex<Connection>,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Event {
     pub id: i64,
     pub timestamp: String,
@@ -55,8 +94,29 @@ pub struct Event {
     pub pid: i64,
     pub duration_secs: f64,
     pub is_idle: i64,
+    /// JSON-encoded array of distinct `"host:port"` remote endpoints observed for
+    /// this window's process, capped at [`MAX_CONNECTIONS_PER_EVENT`].
+    pub connections: String,
+    /// Label of the hotkey-toggled focus session open when this event was recorded,
+    /// `None` if no session was open (see [`crate::hotkey`]).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub focus_label: Option<String>,
+    /// Content-addressed sync id (see [`crate::sync::event_uid`]), `None` until this
+    /// row has been prepared for sync at least once.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub uid: Option<String>,
+    /// Id of the device this event originated on; empty until stamped for sync.
+    #[serde(default)]
+    pub device_id: String,
+    /// Whether this event is already on the configured remote: pushed by this device,
+    /// or pulled from the remote (see [`Db::upsert_remote_event`]).
+    #[serde(default)]
+    pub synced: i64,
 }
 
+/// Maximum number of distinct remote endpoints stored per event.
+pub const MAX_CONNECTIONS_PER_EVENT: usize = 20;
+
 #[derive(Debug, Serialize, Clone)]
 pub struct SummaryRow {
     pub wm_class: String,
@@ -64,6 +124,9 @@ pub struct SummaryRow {
     pub event_count: i64,
     pub first_seen: String,
     pub last_seen: String,
+    /// Name of the [`Category`] whose pattern matched `wm_class`, `None` if unmatched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -78,10 +141,25 @@ pub struct TimelineRow {
     pub title: String,
     pub duration_secs: f64,
     pub is_idle: i64,
+    /// Name of the [`Category`] whose pattern matched `wm_class`, `None` if unmatched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<String>,
 }
 
+/// Total active duration for one resolved category over a query span, used for
+/// dashboard time-per-category rollups.
+#[derive(Debug, Serialize, Clone)]
+pub struct CategoryTotal {
+    pub category: String,
+    pub total_secs: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_formatted: Option<String>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct DailyTotal {
     pub day: String,
@@ -102,6 +180,82 @@ pub struct Category {
     pub color: String,
 }
 
+/// A manually-annotated focus session, e.g. "research" vs. "procrastination", toggled
+/// by the global hotkey. `end_timestamp` is `None` while the session is still open.
+#[derive(Debug, Serialize, Clone)]
+pub struct Annotation {
+    pub id: i64,
+    pub start_timestamp: String,
+    pub end_timestamp: Option<String>,
+    pub label: String,
+}
+
+/// A focus-goal / overuse-alert rule, matched against `wm_class` like [`Category`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rule {
+    #[serde(default)]
+    pub id: i64,
+    pub category_pattern: String,
+    pub daily_threshold_secs: f64,
+    /// Optional active window, e.g. "09:00"..="17:00". Outside this window the rule
+    /// is not evaluated.
+    pub window_start: Option<String>,
+    pub window_end: Option<String>,
+    pub message: String,
+}
+
+/// Render a [`TimeRange`]'s bounds in the `YYYY-MM-DDTHH:MM:SS` format `timestamp`
+/// columns are stored in, for use as bind parameters in a half-open comparison.
+pub(crate) fn format_range(range: TimeRange) -> (String, String) {
+    let fmt = "%Y-%m-%dT%H:%M:%S";
+    (
+        range.start.format(fmt).to_string(),
+        range.end.format(fmt).to_string(),
+    )
+}
+
+/// Compile each category's `wm_class_pattern` into a case-insensitive regex once, so
+/// classifying many rows against the same category set doesn't recompile per row.
+pub(crate) fn compile_category_regexes(categories: &[Category]) -> Vec<(Regex, &Category)> {
+    categories
+        .iter()
+        .filter_map(|c| {
+            Regex::new(&format!("(?i){}", c.wm_class_pattern))
+                .ok()
+                .map(|re| (re, c))
+        })
+        .collect()
+}
+
+/// Find the first compiled category pattern that matches `wm_class`, if any.
+pub(crate) fn classify<'a>(wm_class: &str, compiled: &[(Regex, &'a Category)]) -> Option<&'a Category> {
+    compiled
+        .iter()
+        .find(|(re, _)| re.is_match(wm_class))
+        .map(|(_, c)| *c)
+}
+
+/// Map a row selected as `id, timestamp, end_timestamp, wm_class, title, pid, \
+/// duration_secs, is_idle, connections, uid, device_id, synced, focus_label` into an
+/// [`Event`].
+fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<Event> {
+    Ok(Event {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        end_timestamp: row.get(2)?,
+        wm_class: row.get(3)?,
+        title: row.get(4)?,
+        pid: row.get(5)?,
+        duration_secs: row.get(6)?,
+        is_idle: row.get(7)?,
+        connections: row.get(8)?,
+        uid: row.get(9)?,
+        device_id: row.get(10)?,
+        synced: row.get(11)?,
+        focus_label: row.get(12)?,
+    })
+}
+
 /// Get the database path, respecting `ATRACKER_DATA_DIR`.
 pub fn db_path() -> PathBuf {
     let dir = match std::env::var("ATRACKER_DATA_DIR") {
@@ -118,20 +272,24 @@ fn dirs_home() -> PathBuf {
 }
 
 impl Db {
-    /// Open (or create) the database and run migrations.
-    pub fn open() -> Result<Self, rusqlite::Error> {
+    /// Open (or create) the database, run migrations on a dedicated writer connection,
+    /// and build the read pool alongside it.
+    pub fn open() -> anyhow::Result<Self> {
         let path = db_path();
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).ok();
         }
         info!("Opening database at {}", path.display());
-        let conn = Connection::open(&path)?;
-        conn.execute_batch(SCHEMA)?;
+
+        let mut writer_conn = Connection::open(&path)?;
+        ConnectionCustomizer.on_acquire(&mut writer_conn)?;
+        crate::migrations::migrate(&mut writer_conn)?;
 
         // Seed default categories if empty.
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM categories", [], |r| r.get(0))?;
+        let count: i64 =
+            writer_conn.query_row("SELECT COUNT(*) FROM categories", [], |r| r.get(0))?;
         if count == 0 {
-            let mut stmt = conn.prepare(
+            let mut stmt = writer_conn.prepare(
                 "INSERT INTO categories (name, wm_class_pattern, color) VALUES (?1, ?2, ?3)",
             )?;
             for (name, pattern, color) in DEFAULT_CATEGORIES {
@@ -139,12 +297,19 @@ impl Db {
             }
         }
 
+        let manager = SqliteConnectionManager::file(&path);
+        let read_pool = Pool::builder()
+            .connection_customizer(Box::new(ConnectionCustomizer))
+            .build(manager)?;
+
         Ok(Self {
-            conn: Mutex::new(conn),
+            read_pool,
+            writer: Mutex::new(writer_conn),
         })
     }
 
     /// Insert an activity event and return its ID.
+    #[allow(clippy::too_many_arguments)]
     pub fn insert_event(
         &self,
         timestamp: &str,
@@ -154,83 +319,120 @@ impl Db {
         pid: i64,
         duration_secs: f64,
         is_idle: bool,
-    ) -> Result<i64, rusqlite::Error> {
-        let conn = self.conn.lock().unwrap();
+        connections: &[String],
+        focus_label: Option<&str>,
+    ) -> anyhow::Result<i64> {
+        let connections_json =
+            serde_json::to_string(connections).unwrap_or_else(|_| "[]".to_string());
+        let conn = self.writer.lock().unwrap();
         conn.execute(
-            "INSERT INTO events (timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle as i64],
+            "INSERT INTO events (timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle, connections, focus_label) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                timestamp,
+                end_timestamp,
+                wm_class,
+                title,
+                pid,
+                duration_secs,
+                is_idle as i64,
+                connections_json,
+                focus_label,
+            ],
         )?;
         Ok(conn.last_insert_rowid())
     }
 
-    /// Get all events for a specific date.
-    pub fn get_events(&self, target_date: NaiveDate) -> Result<Vec<Event>, rusqlite::Error> {
-        let day_start = format!("{target_date}T00:00:00");
-        let day_end = format!("{target_date}T23:59:59");
-        let conn = self.conn.lock().unwrap();
+    /// Get all events within a half-open `[start, end)` time range.
+    pub fn get_events(&self, range: TimeRange) -> anyhow::Result<Vec<Event>> {
+        let (start, end) = format_range(range);
+        let conn = self.read_pool.get()?;
         let mut stmt = conn.prepare(
-            "SELECT id, timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle \
-             FROM events WHERE timestamp >= ?1 AND timestamp <= ?2 ORDER BY timestamp",
+            "SELECT id, timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle, \
+             connections, uid, device_id, synced, focus_label \
+             FROM events WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY timestamp",
         )?;
         let rows = stmt
-            .query_map(params![day_start, day_end], |row| {
-                Ok(Event {
-                    id: row.get(0)?,
-                    timestamp: row.get(1)?,
-                    end_timestamp: row.get(2)?,
-                    wm_class: row.get(3)?,
-                    title: row.get(4)?,
-                    pid: row.get(5)?,
-                    duration_secs: row.get(6)?,
-                    is_idle: row.get(7)?,
-                })
-            })?
+            .query_map(params![start, end], row_to_event)?
             .collect::<Result<Vec<_>, _>>()?;
         Ok(rows)
     }
 
-    /// Get per-app usage summary for a specific date.
-    pub fn get_summary(&self, target_date: NaiveDate) -> Result<Vec<SummaryRow>, rusqlite::Error> {
-        let day_start = format!("{target_date}T00:00:00");
-        let day_end = format!("{target_date}T23:59:59");
-        let conn = self.conn.lock().unwrap();
+    /// Get per-app remote endpoint summary for a specific date, derived from each
+    /// event's stored `connections` JSON.
+    pub fn get_connections(
+        &self,
+        target_date: NaiveDate,
+    ) -> anyhow::Result<Vec<(String, Vec<String>)>> {
+        let events = self.get_events(TimeRange::single_day(target_date))?;
+        let mut by_app: std::collections::BTreeMap<String, std::collections::BTreeSet<String>> =
+            std::collections::BTreeMap::new();
+        for event in events {
+            if event.wm_class.is_empty() || event.connections == "[]" {
+                continue;
+            }
+            let endpoints: Vec<String> =
+                serde_json::from_str(&event.connections).unwrap_or_default();
+            by_app.entry(event.wm_class).or_default().extend(endpoints);
+        }
+        Ok(by_app
+            .into_iter()
+            .map(|(app, endpoints)| (app, endpoints.into_iter().collect()))
+            .collect())
+    }
+
+    /// Get per-app usage summary within a half-open `[start, end)` time range, with each
+    /// row classified against the configured categories.
+    pub fn get_summary(&self, range: TimeRange) -> anyhow::Result<Vec<SummaryRow>> {
+        let (start, end) = format_range(range);
+        let categories = self.get_categories()?;
+        let compiled = compile_category_regexes(&categories);
+        let conn = self.read_pool.get()?;
         let mut stmt = conn.prepare(
             "SELECT wm_class, SUM(duration_secs) as total_secs, COUNT(*) as event_count, \
              MIN(timestamp) as first_seen, MAX(end_timestamp) as last_seen \
-             FROM events WHERE timestamp >= ?1 AND timestamp <= ?2 AND is_idle = 0 AND wm_class != '' \
+             FROM events WHERE timestamp >= ?1 AND timestamp < ?2 AND is_idle = 0 AND wm_class != '' \
              GROUP BY wm_class ORDER BY total_secs DESC",
         )?;
-        let rows = stmt
-            .query_map(params![day_start, day_end], |row| {
+        let mut rows = stmt
+            .query_map(params![start, end], |row| {
                 Ok(SummaryRow {
                     wm_class: row.get(0)?,
                     total_secs: row.get(1)?,
                     event_count: row.get(2)?,
                     first_seen: row.get(3)?,
                     last_seen: row.get(4)?,
+                    category: None,
                     color: None,
                     total_formatted: None,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        for row in &mut rows {
+            match classify(&row.wm_class, &compiled) {
+                Some(cat) => {
+                    row.category = Some(cat.name.clone());
+                    row.color = Some(cat.color.clone());
+                }
+                None => row.color = Some(UNCATEGORIZED_COLOR.to_string()),
+            }
+        }
         Ok(rows)
     }
 
-    /// Get timeline blocks for visualization.
-    pub fn get_timeline(
-        &self,
-        target_date: NaiveDate,
-    ) -> Result<Vec<TimelineRow>, rusqlite::Error> {
-        let day_start = format!("{target_date}T00:00:00");
-        let day_end = format!("{target_date}T23:59:59");
-        let conn = self.conn.lock().unwrap();
+    /// Get timeline blocks within a half-open `[start, end)` time range, with each row
+    /// classified against the configured categories.
+    pub fn get_timeline(&self, range: TimeRange) -> anyhow::Result<Vec<TimelineRow>> {
+        let (start, end) = format_range(range);
+        let categories = self.get_categories()?;
+        let compiled = compile_category_regexes(&categories);
+        let conn = self.read_pool.get()?;
         let mut stmt = conn.prepare(
             "SELECT timestamp, end_timestamp, wm_class, title, duration_secs, is_idle \
-             FROM events WHERE timestamp >= ?1 AND timestamp <= ?2 ORDER BY timestamp",
+             FROM events WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY timestamp",
         )?;
-        let rows = stmt
-            .query_map(params![day_start, day_end], |row| {
+        let mut rows = stmt
+            .query_map(params![start, end], |row| {
                 Ok(TimelineRow {
                     timestamp: row.get(0)?,
                     end_timestamp: row.get(1)?,
@@ -238,27 +440,37 @@ impl Db {
                     title: row.get(3)?,
                     duration_secs: row.get(4)?,
                     is_idle: row.get(5)?,
+                    category: None,
                     color: None,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        for row in &mut rows {
+            match classify(&row.wm_class, &compiled) {
+                Some(cat) => {
+                    row.category = Some(cat.name.clone());
+                    row.color = Some(cat.color.clone());
+                }
+                None => row.color = Some(UNCATEGORIZED_COLOR.to_string()),
+            }
+        }
         Ok(rows)
     }
 
-    /// Get daily usage totals over N days.
-    pub fn get_daily_totals(&self, days: i32) -> Result<Vec<DailyTotal>, rusqlite::Error> {
-        let conn = self.conn.lock().unwrap();
+    /// Get daily usage totals within a half-open `[start, end)` time range.
+    pub fn get_daily_totals(&self, range: TimeRange) -> anyhow::Result<Vec<DailyTotal>> {
+        let (start, end) = format_range(range);
+        let conn = self.read_pool.get()?;
         let mut stmt = conn.prepare(
             "SELECT DATE(timestamp) as day, \
              SUM(CASE WHEN is_idle = 0 THEN duration_secs ELSE 0 END) as active_secs, \
              SUM(CASE WHEN is_idle = 1 THEN duration_secs ELSE 0 END) as idle_secs, \
              COUNT(*) as event_count \
-             FROM events WHERE timestamp >= DATE('now', ?1) \
+             FROM events WHERE timestamp >= ?1 AND timestamp < ?2 \
              GROUP BY DATE(timestamp) ORDER BY day DESC",
         )?;
-        let param = format!("-{days} days");
         let rows = stmt
-            .query_map(params![param], |row| {
+            .query_map(params![start, end], |row| {
                 Ok(DailyTotal {
                     day: row.get(0)?,
                     active_secs: row.get(1)?,
@@ -273,8 +485,8 @@ impl Db {
     }
 
     /// Get all categories.
-    pub fn get_categories(&self) -> Result<Vec<Category>, rusqlite::Error> {
-        let conn = self.conn.lock().unwrap();
+    pub fn get_categories(&self) -> anyhow::Result<Vec<Category>> {
+        let conn = self.read_pool.get()?;
         let mut stmt = conn.prepare("SELECT id, name, wm_class_pattern, color FROM categories ORDER BY name")?;
         let rows = stmt
             .query_map([], |row| {
@@ -288,4 +500,270 @@ impl Db {
             .collect::<Result<Vec<_>, _>>()?;
         Ok(rows)
     }
+
+    /// Start a new focus-session annotation, returning its ID.
+    pub fn start_annotation(&self, label: &str, start_timestamp: &str) -> anyhow::Result<i64> {
+        let conn = self.writer.lock().unwrap();
+        conn.execute(
+            "INSERT INTO annotations (start_timestamp, label) VALUES (?1, ?2)",
+            params![start_timestamp, label],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Close an open annotation by stamping its end timestamp.
+    pub fn end_annotation(&self, id: i64, end_timestamp: &str) -> anyhow::Result<()> {
+        let conn = self.writer.lock().unwrap();
+        conn.execute(
+            "UPDATE annotations SET end_timestamp = ?1 WHERE id = ?2",
+            params![end_timestamp, id],
+        )?;
+        Ok(())
+    }
+
+    /// Get annotations overlapping a half-open `[start, end)` time range, including any
+    /// still-open session.
+    pub fn get_annotations(&self, range: TimeRange) -> anyhow::Result<Vec<Annotation>> {
+        let (start, end) = format_range(range);
+        let conn = self.read_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, start_timestamp, end_timestamp, label FROM annotations \
+             WHERE start_timestamp < ?2 AND (end_timestamp IS NULL OR end_timestamp >= ?1) \
+             ORDER BY start_timestamp",
+        )?;
+        let rows = stmt
+            .query_map(params![start, end], |row| {
+                Ok(Annotation {
+                    id: row.get(0)?,
+                    start_timestamp: row.get(1)?,
+                    end_timestamp: row.get(2)?,
+                    label: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Get all configured focus/overuse rules.
+    pub fn get_rules(&self) -> anyhow::Result<Vec<Rule>> {
+        let conn = self.read_pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, category_pattern, daily_threshold_secs, window_start, window_end, message \
+             FROM rules ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Rule {
+                    id: row.get(0)?,
+                    category_pattern: row.get(1)?,
+                    daily_threshold_secs: row.get(2)?,
+                    window_start: row.get(3)?,
+                    window_end: row.get(4)?,
+                    message: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Stream all events (optionally bounded by `since`/`until`) to `writer` as JSON
+    /// Lines, one [`Event`] per line. Returns the number of events written.
+    pub fn export_events(
+        &self,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+        writer: &mut impl Write,
+    ) -> anyhow::Result<usize> {
+        let conn = self.read_pool.get()?;
+        let mut sql = "SELECT id, timestamp, end_timestamp, wm_class, title, pid, duration_secs, \
+                       is_idle, connections, uid, device_id, synced, focus_label \
+                       FROM events WHERE 1=1"
+            .to_string();
+        let mut bounds = Vec::new();
+        if let Some(since) = since {
+            sql.push_str(" AND timestamp >= ?");
+            bounds.push(format!("{since}T00:00:00"));
+        }
+        if let Some(until) = until {
+            sql.push_str(" AND timestamp <= ?");
+            bounds.push(format!("{until}T23:59:59"));
+        }
+        sql.push_str(" ORDER BY timestamp");
+
+        let mut stmt = conn.prepare(&sql)?;
+        let params = rusqlite::params_from_iter(bounds.iter());
+        let rows = stmt.query_map(params, row_to_event)?;
+
+        let mut count = 0;
+        for row in rows {
+            let event = row?;
+            serde_json::to_writer(&mut *writer, &event)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Read JSON Lines events from `reader` and batch-insert them, committing every
+    /// `batch_size` rows so a large import doesn't hold one giant transaction or blow
+    /// memory. Malformed lines are logged and skipped rather than aborting the import.
+    pub fn import_events(&self, reader: impl BufRead, batch_size: usize) -> anyhow::Result<usize> {
+        let mut conn = self.writer.lock().unwrap();
+        let mut imported = 0;
+        let mut tx = conn.transaction()?;
+
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: Event = match serde_json::from_str(&line) {
+                Ok(e) => e,
+                Err(e) => {
+                    warn!("Skipping malformed line {}: {e}", line_no + 1);
+                    continue;
+                }
+            };
+            if event.duration_secs < 0.0 || event.timestamp.is_empty() || event.end_timestamp.is_empty() {
+                warn!("Skipping invalid event at line {}: bad timestamp/duration", line_no + 1);
+                continue;
+            }
+
+            tx.execute(
+                "INSERT INTO events (timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle, connections, focus_label) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    event.timestamp,
+                    event.end_timestamp,
+                    event.wm_class,
+                    event.title,
+                    event.pid,
+                    event.duration_secs,
+                    event.is_idle,
+                    event.connections,
+                    event.focus_label,
+                ],
+            )?;
+            imported += 1;
+
+            if imported % batch_size == 0 {
+                tx.commit()?;
+                tx = conn.transaction()?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(imported)
+    }
+
+    /// Insert a new rule and return its ID.
+    pub fn insert_rule(&self, rule: &Rule) -> anyhow::Result<i64> {
+        let conn = self.writer.lock().unwrap();
+        conn.execute(
+            "INSERT INTO rules (category_pattern, daily_threshold_secs, window_start, window_end, message) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                rule.category_pattern,
+                rule.daily_threshold_secs,
+                rule.window_start,
+                rule.window_end,
+                rule.message,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Get up to `limit` events this device hasn't pushed to any remote yet, stamping
+    /// each with a content-addressed `uid` and this device's id on the way out (so
+    /// re-running sync after a crash mid-push re-sends the same ids, not new ones).
+    pub fn get_unsynced_events(&self, device_id: &str, limit: usize) -> anyhow::Result<Vec<Event>> {
+        let conn = self.writer.lock().unwrap();
+        conn.execute(
+            "UPDATE events SET device_id = ?1 WHERE synced = 0 AND device_id = ''",
+            params![device_id],
+        )?;
+        let mut stmt = conn.prepare(
+            "SELECT id, timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle, \
+             connections, uid, device_id, synced, focus_label \
+             FROM events WHERE synced = 0 ORDER BY id LIMIT ?1",
+        )?;
+        let mut events = stmt
+            .query_map(params![limit as i64], row_to_event)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for event in &mut events {
+            if event.uid.is_none() {
+                let uid = crate::sync::event_uid(
+                    &event.device_id,
+                    &event.timestamp,
+                    &event.wm_class,
+                    event.pid,
+                    event.id,
+                );
+                conn.execute("UPDATE events SET uid = ?1 WHERE id = ?2", params![uid, event.id])?;
+                event.uid = Some(uid);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Mark the given local event ids as pushed to the remote.
+    pub fn mark_synced(&self, ids: &[i64]) -> anyhow::Result<()> {
+        let conn = self.writer.lock().unwrap();
+        for id in ids {
+            conn.execute("UPDATE events SET synced = 1 WHERE id = ?1", params![id])?;
+        }
+        Ok(())
+    }
+
+    /// Insert an event received from a remote, ignoring it if its `uid` is already
+    /// present (either pushed by us originally, or pulled in a previous sync).
+    pub fn upsert_remote_event(&self, event: &Event) -> anyhow::Result<()> {
+        let conn = self.writer.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO events \
+             (timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle, connections, uid, device_id, synced, focus_label) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1, ?11)",
+            params![
+                event.timestamp,
+                event.end_timestamp,
+                event.wm_class,
+                event.title,
+                event.pid,
+                event.duration_secs,
+                event.is_idle,
+                event.connections,
+                event.uid,
+                event.device_id,
+                event.focus_label,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the last-pulled event uid for `remote`, `None` if we've never synced with it.
+    pub fn get_sync_cursor(&self, remote: &str) -> anyhow::Result<Option<String>> {
+        use rusqlite::OptionalExtension;
+        let conn = self.read_pool.get()?;
+        let cursor = conn
+            .query_row(
+                "SELECT last_pulled_uid FROM sync_state WHERE remote = ?1",
+                params![remote],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(cursor)
+    }
+
+    /// Record `uid` as the last event pulled from `remote`.
+    pub fn set_sync_cursor(&self, remote: &str, uid: &str) -> anyhow::Result<()> {
+        let conn = self.writer.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sync_state (remote, last_pulled_uid, last_synced_at) VALUES (?1, ?2, datetime('now')) \
+             ON CONFLICT(remote) DO UPDATE SET last_pulled_uid = excluded.last_pulled_uid, \
+             last_synced_at = excluded.last_synced_at",
+            params![remote, uid],
+        )?;
+        Ok(())
+    }
 }
@@ -0,0 +1,81 @@
+//! Global hotkey listener for manual session annotation and Pomodoro marks.
+//!
+//! Atracker doesn't grab keyboard input itself — like the GNOME window-tracker
+//! extension it already depends on for `get_active_window`, the actual key combo is
+//! bound by the user's desktop environment (a custom keyboard shortcut running e.g.
+//! `gdbus call --session --dest org.atracker.Hotkey --object-path /org/atracker/Hotkey
+//! --method org.atracker.Hotkey.ToggleFocusSession "research"`). This module just
+//! exposes that D-Bus service and keeps the open/closed session state.
+
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+use zbus::{interface, Connection};
+
+use crate::db::Db;
+
+/// The label of the focus session currently open (if any), shared with
+/// [`crate::watcher::Watcher`] so it can stamp it onto every event recorded while the
+/// session is open, in addition to the `annotations` row this module itself owns.
+pub type SharedFocusLabel = Arc<RwLock<Option<String>>>;
+
+/// Create an empty shared focus-label slot.
+pub fn new_shared_focus_label() -> SharedFocusLabel {
+    Arc::new(RwLock::new(None))
+}
+
+struct HotkeyService {
+    db: Arc<Db>,
+    open_annotation: Option<i64>,
+    focus_label: SharedFocusLabel,
+}
+
+#[interface(name = "org.atracker.Hotkey")]
+impl HotkeyService {
+    /// Toggle the current focus session. If one is open, closes it; otherwise starts
+    /// a new one under `label`. Returns whether a session is now active.
+    async fn toggle_focus_session(&mut self, label: String) -> bool {
+        let now = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        match self.open_annotation.take() {
+            Some(id) => {
+                if let Err(e) = self.db.end_annotation(id, &now) {
+                    warn!("Failed to close annotation {id}: {e}");
+                }
+                *self.focus_label.write().unwrap() = None;
+                info!("Focus session closed");
+                false
+            }
+            None => match self.db.start_annotation(&label, &now) {
+                Ok(id) => {
+                    self.open_annotation = Some(id);
+                    *self.focus_label.write().unwrap() = Some(label.clone());
+                    info!("Focus session started: {label}");
+                    true
+                }
+                Err(e) => {
+                    warn!("Failed to start annotation: {e}");
+                    false
+                }
+            },
+        }
+    }
+}
+
+/// Register the hotkey D-Bus service and serve it until the process exits. Runs
+/// alongside `Watcher::run` on the same tokio runtime.
+pub async fn run(db: Arc<Db>, focus_label: SharedFocusLabel) -> zbus::Result<()> {
+    let service = HotkeyService {
+        db,
+        open_annotation: None,
+        focus_label,
+    };
+    let conn = Connection::session().await?;
+    conn.object_server()
+        .at("/org/atracker/Hotkey", service)
+        .await?;
+    conn.request_name("org.atracker.Hotkey").await?;
+
+    info!("Hotkey service listening on org.atracker.Hotkey");
+    // Keep the connection alive for the lifetime of the daemon.
+    std::future::pending::<()>().await;
+    Ok(())
+}
@@ -1,7 +1,16 @@
 //! CLI entry point for atracker (Rust port).
 
 mod api;
+mod bus;
+mod config;
+mod daterange;
 mod db;
+mod hotkey;
+mod migrations;
+mod notifier;
+mod postgres_repo;
+mod repository;
+mod sync;
 mod watcher;
 
 use clap::{Parser, Subcommand};
@@ -21,6 +30,26 @@ enum Commands {
     Start,
     /// Check if the daemon is running
     Status,
+    /// Export events as JSON Lines to stdout
+    Export {
+        /// Only include events on or after this date (YYYY-MM-DD). Ignored if `--range`
+        /// is given.
+        #[arg(long)]
+        since: Option<String>,
+        /// Only include events on or before this date (YYYY-MM-DD). Ignored if
+        /// `--range` is given.
+        #[arg(long)]
+        until: Option<String>,
+        /// Human-friendly range instead of `--since`/`--until`, e.g. `today`,
+        /// `last 7d`, `this-week`, or an explicit `2024-01-01..2024-01-31`.
+        #[arg(long)]
+        range: Option<String>,
+    },
+    /// Import events from JSON Lines on stdin
+    Import,
+    /// Push locally-unsynced events and pull remote ones (requires sync_server_url /
+    /// sync_key in config.toml)
+    Sync,
 }
 
 #[tokio::main]
@@ -39,21 +68,37 @@ async fn main() -> anyhow::Result<()> {
             info!("Dashboard will be available at http://localhost:8932");
 
             let db = Arc::new(db::Db::open()?);
+            let repo = build_repository(db.clone())?;
+            let bus = bus::new_bus();
+            let current = bus::new_shared_current();
+            let focus_label = hotkey::new_shared_focus_label();
 
             // Run the actix-web API server on a dedicated OS thread with its own runtime,
             // because actix-web's HttpServer future is !Send and cannot be tokio::spawn'd.
             let api_db = db.clone();
+            let api_repo = repo.clone();
+            let api_bus = bus.clone();
+            let api_current = current.clone();
             std::thread::spawn(move || {
                 let rt = actix_web::rt::System::new();
                 rt.block_on(async move {
-                    if let Err(e) = api::run_server(api_db, 8932).await {
+                    if let Err(e) = api::run_server(api_db, api_repo, api_bus, api_current, 8932).await {
                         eprintln!("API server error: {e}");
                     }
                 });
             });
 
+            // Hotkey service runs alongside the watcher on the same tokio runtime.
+            let hotkey_db = db.clone();
+            let hotkey_focus_label = focus_label.clone();
+            tokio::spawn(async move {
+                if let Err(e) = hotkey::run(hotkey_db, hotkey_focus_label).await {
+                    eprintln!("Hotkey service error: {e}");
+                }
+            });
+
             // Run watcher in foreground on the tokio runtime.
-            let mut w = watcher::Watcher::new(db);
+            let mut w = watcher::Watcher::new(db, repo, bus, current, focus_label);
             w.run().await?;
         }
 
@@ -78,7 +123,53 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+
+        Commands::Export { since, until, range } => {
+            let db = db::Db::open()?;
+            let (since, until) = if let Some(r) = range {
+                let tr = daterange::parse_time_range(&r).map_err(|e| anyhow::anyhow!(e))?;
+                (Some(tr.start_day()), Some(tr.end_day()))
+            } else {
+                let since = since
+                    .map(|s| chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d"))
+                    .transpose()?;
+                let until = until
+                    .map(|s| chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d"))
+                    .transpose()?;
+                (since, until)
+            };
+            let mut stdout = std::io::stdout().lock();
+            let count = db.export_events(since, until, &mut stdout)?;
+            eprintln!("Exported {count} events");
+        }
+
+        Commands::Import => {
+            let db = db::Db::open()?;
+            let stdin = std::io::stdin().lock();
+            let count = db.import_events(stdin, 1000)?;
+            eprintln!("Imported {count} events");
+        }
+
+        Commands::Sync => {
+            let db = Arc::new(db::Db::open()?);
+            let config = config::load();
+            sync::run(db, &config).await?;
+        }
     }
 
     Ok(())
 }
+
+/// Pick the backend for the core event-metrics path: Postgres if `ATRACKER_DATABASE_URL`
+/// is set (for a household/team sharing one instance), otherwise the same local SQLite
+/// handle used for rules, annotations, the hotkey service, and sync bookkeeping.
+fn build_repository(db: Arc<db::Db>) -> anyhow::Result<Arc<dyn repository::Repository + Send + Sync>> {
+    match std::env::var("ATRACKER_DATABASE_URL") {
+        Ok(url) if !url.is_empty() => {
+            info!("Using Postgres-backed event storage (ATRACKER_DATABASE_URL set)");
+            let repo = Arc::new(postgres_repo::PgRepository::connect(&url)?);
+            Ok(repo as Arc<dyn repository::Repository + Send + Sync>)
+        }
+        _ => Ok(db as Arc<dyn repository::Repository + Send + Sync>),
+    }
+}
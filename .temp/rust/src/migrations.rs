@@ -0,0 +1,127 @@
+//! Versioned schema migrations, tracked via `PRAGMA user_version` so the `events`/
+//! `categories` tables (and friends) can evolve without risking divergence between
+//! old and new databases.
+
+use rusqlite::Connection;
+use tracing::info;
+
+/// Current schema version this binary expects. Bump this and append a new entry to
+/// [`MIGRATIONS`] whenever the schema changes.
+pub const DB_VERSION: u32 = 6;
+
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+/// Ordered migration steps. Index `i` (0-based) corresponds to schema version `i + 1`.
+const MIGRATIONS: &[Migration] = &[
+    migration_001_baseline,
+    migration_002_connections,
+    migration_003_rules,
+    migration_004_annotations,
+    migration_005_sync,
+    migration_006_focus_label,
+];
+
+/// The schema as it existed before this migration framework did. Must stay exactly the
+/// original `events`/`categories` tables — a database stamped at `user_version=0` is
+/// assumed to already have this, so changing it here would desync that assumption.
+fn migration_001_baseline(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(crate::db::BASELINE_SCHEMA)
+}
+
+/// Adds the `connections` column used to attribute a window's TCP/UDP remote endpoints
+/// to its event.
+fn migration_002_connections(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE events ADD COLUMN connections TEXT NOT NULL DEFAULT '[]';")
+}
+
+/// Adds the `rules` table backing the focus-goal rules engine.
+fn migration_003_rules(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS rules (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             category_pattern TEXT NOT NULL,
+             daily_threshold_secs REAL NOT NULL,
+             window_start TEXT,
+             window_end TEXT,
+             message TEXT NOT NULL
+         );",
+    )
+}
+
+/// Adds the `annotations` table backing hotkey-triggered focus-session labels.
+fn migration_004_annotations(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS annotations (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             start_timestamp TEXT NOT NULL,
+             end_timestamp TEXT,
+             label TEXT NOT NULL
+         );",
+    )
+}
+
+/// Adds the columns and table needed for multi-machine sync: a content-addressed `uid`
+/// and owning `device_id` per event, plus a `sync_state` table tracking, per remote, how
+/// far we've pulled/pushed.
+fn migration_005_sync(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE events ADD COLUMN uid TEXT;
+         ALTER TABLE events ADD COLUMN device_id TEXT NOT NULL DEFAULT '';
+         ALTER TABLE events ADD COLUMN synced INTEGER NOT NULL DEFAULT 0;
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_events_uid ON events(uid);
+         CREATE TABLE IF NOT EXISTS sync_state (
+             remote TEXT PRIMARY KEY,
+             last_pulled_uid TEXT,
+             last_synced_at TEXT
+         );",
+    )
+}
+
+/// Adds the `focus_label` column stamping each event with the hotkey-toggled focus
+/// session (if any) open while it was recorded; see [`crate::hotkey`].
+fn migration_006_focus_label(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE events ADD COLUMN focus_label TEXT;")
+}
+
+/// Bring `conn`'s schema up to [`DB_VERSION`], applying each pending migration inside
+/// its own transaction and bumping `PRAGMA user_version` immediately after so a crash
+/// mid-upgrade leaves the database at a recoverable version rather than a half-applied
+/// one. Fails loudly if the database is *newer* than this binary supports.
+pub fn migrate(conn: &mut Connection) -> anyhow::Result<()> {
+    let events_table_exists: bool = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='events'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )? > 0;
+    let stored_version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    if stored_version > DB_VERSION {
+        anyhow::bail!(
+            "database schema version {stored_version} is newer than this binary supports \
+             (max {DB_VERSION}) — refusing to run, please upgrade atracker"
+        );
+    }
+
+    // A database created before this migration framework existed has no stamped
+    // version, but already has the baseline schema — treat it as version 1 rather
+    // than re-running (harmless, but noisy) `CREATE TABLE IF NOT EXISTS` from scratch.
+    let effective_version = if stored_version == 0 && events_table_exists {
+        1
+    } else {
+        stored_version
+    };
+
+    for (idx, step) in MIGRATIONS.iter().enumerate() {
+        let step_version = (idx + 1) as u32;
+        if step_version <= effective_version {
+            continue;
+        }
+        let tx = conn.transaction()?;
+        step(&tx)?;
+        tx.execute_batch(&format!("PRAGMA user_version = {step_version}"))?;
+        tx.commit()?;
+        info!("Applied migration {step_version}/{DB_VERSION}");
+    }
+
+    Ok(())
+}
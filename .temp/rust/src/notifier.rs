@@ -0,0 +1,35 @@
+//! Desktop notifications via `org.freedesktop.Notifications`, following the same
+//! direct D-Bus call pattern the watcher already uses for Mutter's idle monitor.
+
+use tracing::warn;
+use zbus::Connection;
+
+/// Fire a desktop notification with the given summary and body. Failures are logged
+/// and swallowed — a missing notification daemon must never take down the watcher.
+pub async fn notify(summary: &str, body: &str) {
+    if let Err(e) = try_notify(summary, body).await {
+        warn!("Failed to send desktop notification: {e}");
+    }
+}
+
+async fn try_notify(summary: &str, body: &str) -> zbus::Result<()> {
+    let conn = Connection::session().await?;
+    conn.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &(
+            "atracker",
+            0u32,
+            "",
+            summary,
+            body,
+            Vec::<&str>::new(),
+            std::collections::HashMap::<&str, zbus::zvariant::Value>::new(),
+            5000i32,
+        ),
+    )
+    .await?;
+    Ok(())
+}
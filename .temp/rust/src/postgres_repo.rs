@@ -0,0 +1,243 @@
+//! PostgreSQL-backed [`Repository`], selected via `ATRACKER_DATABASE_URL`, for a
+//! household or team that wants every tracker pointed at one shared instance instead of
+//! each machine's local SQLite file. Mirrors `db.rs`'s r2d2 pooling, but with a
+//! blocking `postgres` client instead of `rusqlite`/`r2d2_sqlite`.
+
+use crate::daterange::TimeRange;
+use crate::db::{
+    classify, compile_category_regexes, format_range, Category, DailyTotal, Event, SummaryRow,
+    TimelineRow, UNCATEGORIZED_COLOR,
+};
+use crate::repository::Repository;
+use postgres::NoTls;
+use r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
+use tracing::info;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS events (
+    id BIGSERIAL PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    end_timestamp TEXT NOT NULL,
+    wm_class TEXT NOT NULL DEFAULT '',
+    title TEXT NOT NULL DEFAULT '',
+    pid BIGINT NOT NULL DEFAULT 0,
+    duration_secs DOUBLE PRECISION NOT NULL DEFAULT 0,
+    is_idle INTEGER NOT NULL DEFAULT 0,
+    connections TEXT NOT NULL DEFAULT '[]',
+    focus_label TEXT
+);
+
+CREATE TABLE IF NOT EXISTS categories (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    wm_class_pattern TEXT NOT NULL,
+    color TEXT NOT NULL DEFAULT '#3b82f6'
+);
+
+CREATE INDEX IF NOT EXISTS idx_pg_events_timestamp ON events(timestamp);
+"#;
+
+pub struct PgRepository {
+    pool: Pool<PostgresConnectionManager<NoTls>>,
+}
+
+impl PgRepository {
+    /// Connect to `database_url`, ensure the schema exists, and seed the same default
+    /// categories [`crate::db::Db::open`] does — otherwise a fresh Postgres-backed
+    /// deployment's `categories` table stays empty forever (there's no API to insert
+    /// one), which makes every classification-dependent read always fall back to
+    /// "Uncategorized".
+    pub fn connect(database_url: &str) -> anyhow::Result<Self> {
+        let config: postgres::Config = database_url.parse()?;
+        let manager = PostgresConnectionManager::new(config, NoTls);
+        let pool = Pool::new(manager)?;
+        let mut conn = pool.get()?;
+        conn.batch_execute(SCHEMA)?;
+
+        let count: i64 = conn.query_one("SELECT COUNT(*) FROM categories", &[])?.get(0);
+        if count == 0 {
+            for (name, pattern, color) in crate::db::DEFAULT_CATEGORIES {
+                conn.execute(
+                    "INSERT INTO categories (name, wm_class_pattern, color) VALUES ($1, $2, $3)",
+                    &[name, pattern, color],
+                )?;
+            }
+        }
+
+        info!("Connected to Postgres event store");
+        Ok(Self { pool })
+    }
+}
+
+impl Repository for PgRepository {
+    fn insert_event(
+        &self,
+        timestamp: &str,
+        end_timestamp: &str,
+        wm_class: &str,
+        title: &str,
+        pid: i64,
+        duration_secs: f64,
+        is_idle: bool,
+        connections: &[String],
+        focus_label: Option<&str>,
+    ) -> anyhow::Result<i64> {
+        let connections_json =
+            serde_json::to_string(connections).unwrap_or_else(|_| "[]".to_string());
+        let mut conn = self.pool.get()?;
+        let row = conn.query_one(
+            "INSERT INTO events (timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle, connections, focus_label) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+            &[
+                &timestamp,
+                &end_timestamp,
+                &wm_class,
+                &title,
+                &pid,
+                &duration_secs,
+                &(is_idle as i32),
+                &connections_json,
+                &focus_label,
+            ],
+        )?;
+        Ok(row.get(0))
+    }
+
+    fn get_events(&self, range: TimeRange) -> anyhow::Result<Vec<Event>> {
+        let (start, end) = format_range(range);
+        let mut conn = self.pool.get()?;
+        let rows = conn.query(
+            "SELECT id, timestamp, end_timestamp, wm_class, title, pid, duration_secs, is_idle, connections, focus_label \
+             FROM events WHERE timestamp >= $1 AND timestamp < $2 ORDER BY timestamp",
+            &[&start, &end],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| Event {
+                id: row.get(0),
+                timestamp: row.get(1),
+                end_timestamp: row.get(2),
+                wm_class: row.get(3),
+                title: row.get(4),
+                pid: row.get(5),
+                duration_secs: row.get(6),
+                is_idle: row.get::<_, i32>(7) as i64,
+                connections: row.get(8),
+                focus_label: row.get(9),
+                // Sync is a local-first, SQLite-only feature; Postgres-backed rows
+                // don't participate in it.
+                uid: None,
+                device_id: String::new(),
+                synced: 0,
+            })
+            .collect())
+    }
+
+    fn get_summary(&self, range: TimeRange) -> anyhow::Result<Vec<SummaryRow>> {
+        let (start, end) = format_range(range);
+        let categories = self.get_categories()?;
+        let compiled = compile_category_regexes(&categories);
+        let mut conn = self.pool.get()?;
+        let rows = conn.query(
+            "SELECT wm_class, SUM(duration_secs), COUNT(*), MIN(timestamp), MAX(end_timestamp) \
+             FROM events WHERE timestamp >= $1 AND timestamp < $2 AND is_idle = 0 AND wm_class != '' \
+             GROUP BY wm_class ORDER BY SUM(duration_secs) DESC",
+            &[&start, &end],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let wm_class: String = row.get(0);
+                let (category, color) = match classify(&wm_class, &compiled) {
+                    Some(cat) => (Some(cat.name.clone()), Some(cat.color.clone())),
+                    None => (None, Some(UNCATEGORIZED_COLOR.to_string())),
+                };
+                SummaryRow {
+                    wm_class,
+                    total_secs: row.get(1),
+                    event_count: row.get(2),
+                    first_seen: row.get(3),
+                    last_seen: row.get(4),
+                    category,
+                    color,
+                    total_formatted: None,
+                }
+            })
+            .collect())
+    }
+
+    fn get_timeline(&self, range: TimeRange) -> anyhow::Result<Vec<TimelineRow>> {
+        let (start, end) = format_range(range);
+        let categories = self.get_categories()?;
+        let compiled = compile_category_regexes(&categories);
+        let mut conn = self.pool.get()?;
+        let rows = conn.query(
+            "SELECT timestamp, end_timestamp, wm_class, title, duration_secs, is_idle \
+             FROM events WHERE timestamp >= $1 AND timestamp < $2 ORDER BY timestamp",
+            &[&start, &end],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let wm_class: String = row.get(2);
+                let (category, color) = match classify(&wm_class, &compiled) {
+                    Some(cat) => (Some(cat.name.clone()), Some(cat.color.clone())),
+                    None => (None, Some(UNCATEGORIZED_COLOR.to_string())),
+                };
+                TimelineRow {
+                    timestamp: row.get(0),
+                    end_timestamp: row.get(1),
+                    wm_class,
+                    title: row.get(3),
+                    duration_secs: row.get(4),
+                    is_idle: row.get::<_, i32>(5) as i64,
+                    category,
+                    color,
+                }
+            })
+            .collect())
+    }
+
+    fn get_daily_totals(&self, range: TimeRange) -> anyhow::Result<Vec<DailyTotal>> {
+        let (start, end) = format_range(range);
+        let mut conn = self.pool.get()?;
+        let rows = conn.query(
+            "SELECT to_char(timestamp::timestamp, 'YYYY-MM-DD') as day, \
+             SUM(CASE WHEN is_idle = 0 THEN duration_secs ELSE 0 END), \
+             SUM(CASE WHEN is_idle = 1 THEN duration_secs ELSE 0 END), \
+             COUNT(*) \
+             FROM events WHERE timestamp >= $1 AND timestamp < $2 \
+             GROUP BY day ORDER BY day DESC",
+            &[&start, &end],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| DailyTotal {
+                day: row.get(0),
+                active_secs: row.get(1),
+                idle_secs: row.get(2),
+                event_count: row.get(3),
+                active_formatted: None,
+                idle_formatted: None,
+            })
+            .collect())
+    }
+
+    fn get_categories(&self) -> anyhow::Result<Vec<Category>> {
+        let mut conn = self.pool.get()?;
+        let rows = conn.query(
+            "SELECT id, name, wm_class_pattern, color FROM categories ORDER BY name",
+            &[],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| Category {
+                id: row.get(0),
+                name: row.get(1),
+                wm_class_pattern: row.get(2),
+                color: row.get(3),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,120 @@
+//! Backend-agnostic event storage, so a household or team can point every tracker at
+//! one shared instance instead of each machine's local SQLite file. Implementations
+//! encapsulate their own SQL dialect, e.g. for grouping events by day (SQLite's
+//! `DATE(timestamp)` vs. Postgres' `to_char(timestamp::timestamp, 'YYYY-MM-DD')`).
+//!
+//! Rules, annotations, the hotkey service, and sync bookkeeping are local-first by
+//! design and always go through the concrete SQLite [`crate::db::Db`] directly — only
+//! the core event-metrics path is pluggable.
+
+use crate::daterange::TimeRange;
+use crate::db::{Category, CategoryTotal, DailyTotal, Event, SummaryRow, TimelineRow};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+pub trait Repository {
+    /// Insert an activity event and return its ID.
+    #[allow(clippy::too_many_arguments)]
+    fn insert_event(
+        &self,
+        timestamp: &str,
+        end_timestamp: &str,
+        wm_class: &str,
+        title: &str,
+        pid: i64,
+        duration_secs: f64,
+        is_idle: bool,
+        connections: &[String],
+        focus_label: Option<&str>,
+    ) -> anyhow::Result<i64>;
+
+    /// Get all events within a half-open `[start, end)` time range.
+    fn get_events(&self, range: TimeRange) -> anyhow::Result<Vec<Event>>;
+
+    /// Get per-app usage summary within a half-open `[start, end)` time range.
+    fn get_summary(&self, range: TimeRange) -> anyhow::Result<Vec<SummaryRow>>;
+
+    /// Get timeline blocks within a half-open `[start, end)` time range.
+    fn get_timeline(&self, range: TimeRange) -> anyhow::Result<Vec<TimelineRow>>;
+
+    /// Get daily usage totals within a half-open `[start, end)` time range.
+    fn get_daily_totals(&self, range: TimeRange) -> anyhow::Result<Vec<DailyTotal>>;
+
+    /// Get all categories.
+    fn get_categories(&self) -> anyhow::Result<Vec<Category>>;
+
+    /// Get total active duration grouped by resolved category for `date`, with an
+    /// "Uncategorized" bucket for events whose `wm_class` matched no category pattern.
+    /// Built on top of [`Repository::get_summary`], which already does the per-row
+    /// classification, so every backend gets this rollup for free.
+    fn get_category_totals(&self, date: NaiveDate) -> anyhow::Result<Vec<CategoryTotal>> {
+        let summary = self.get_summary(TimeRange::single_day(date))?;
+        let mut totals: BTreeMap<String, (f64, Option<String>)> = BTreeMap::new();
+        for row in summary {
+            let key = row.category.unwrap_or_else(|| "Uncategorized".to_string());
+            let entry = totals.entry(key).or_insert((0.0, row.color));
+            entry.0 += row.total_secs;
+        }
+        let mut rows: Vec<CategoryTotal> = totals
+            .into_iter()
+            .map(|(category, (total_secs, color))| CategoryTotal {
+                category,
+                total_secs,
+                color,
+                total_formatted: None,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.total_secs.partial_cmp(&a.total_secs).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(rows)
+    }
+}
+
+/// The local SQLite database is the default `Repository` implementation; its inherent
+/// methods already have this exact shape, so each trait method just delegates.
+impl Repository for crate::db::Db {
+    fn insert_event(
+        &self,
+        timestamp: &str,
+        end_timestamp: &str,
+        wm_class: &str,
+        title: &str,
+        pid: i64,
+        duration_secs: f64,
+        is_idle: bool,
+        connections: &[String],
+        focus_label: Option<&str>,
+    ) -> anyhow::Result<i64> {
+        crate::db::Db::insert_event(
+            self,
+            timestamp,
+            end_timestamp,
+            wm_class,
+            title,
+            pid,
+            duration_secs,
+            is_idle,
+            connections,
+            focus_label,
+        )
+    }
+
+    fn get_events(&self, range: TimeRange) -> anyhow::Result<Vec<Event>> {
+        crate::db::Db::get_events(self, range)
+    }
+
+    fn get_summary(&self, range: TimeRange) -> anyhow::Result<Vec<SummaryRow>> {
+        crate::db::Db::get_summary(self, range)
+    }
+
+    fn get_timeline(&self, range: TimeRange) -> anyhow::Result<Vec<TimelineRow>> {
+        crate::db::Db::get_timeline(self, range)
+    }
+
+    fn get_daily_totals(&self, range: TimeRange) -> anyhow::Result<Vec<DailyTotal>> {
+        crate::db::Db::get_daily_totals(self, range)
+    }
+
+    fn get_categories(&self) -> anyhow::Result<Vec<Category>> {
+        crate::db::Db::get_categories(self)
+    }
+}
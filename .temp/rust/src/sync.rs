@@ -0,0 +1,150 @@
+//! Opt-in multi-machine sync, modeled on atuin: events are content-addressed so pushing
+//! the same row twice is harmless, encrypted client-side before upload so the remote
+//! only ever sees opaque blobs, and merged locally by dropping duplicate uids.
+
+use crate::config::Config;
+use crate::db::{Db, Event};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Number of unsynced events pushed per `atracker sync` invocation.
+const PUSH_BATCH_SIZE: usize = 500;
+
+/// Derive a stable content-addressed id for an event from its immutable fields, so
+/// re-syncing after a partial push re-sends the same id rather than creating a
+/// duplicate on the remote. Mixes in the local row id too: two events can otherwise
+/// share device/timestamp/wm_class/pid (e.g. the same app regaining focus twice within
+/// one clock second), and `id` is the one field guaranteed not to collide between them.
+pub fn event_uid(device_id: &str, timestamp: &str, wm_class: &str, pid: i64, id: i64) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(device_id.as_bytes());
+    hasher.update(timestamp.as_bytes());
+    hasher.update(wm_class.as_bytes());
+    hasher.update(&pid.to_le_bytes());
+    hasher.update(&id.to_le_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Get (or create) this machine's stable device id, persisted next to the database so
+/// it survives restarts and reinstalls of the binary.
+pub fn device_id() -> anyhow::Result<String> {
+    let path = crate::db::db_path().with_file_name("device_id");
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        let id = existing.trim().to_string();
+        if !id.is_empty() {
+            return Ok(id);
+        }
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).ok();
+    }
+    std::fs::write(&path, &id)?;
+    Ok(id)
+}
+
+/// An event payload as it travels over the wire: opaque to the server, since only the
+/// holder of `sync_key` can decrypt `ciphertext` back into an [`Event`].
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedEvent {
+    uid: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn cipher_from_passphrase(passphrase: &str) -> XChaCha20Poly1305 {
+    let key = blake3::hash(passphrase.as_bytes());
+    XChaCha20Poly1305::new(key.as_bytes().into())
+}
+
+fn encrypt_event(cipher: &XChaCha20Poly1305, event: &Event) -> anyhow::Result<EncryptedEvent> {
+    let uid = event
+        .uid
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("event {} has no uid — call get_unsynced_events first", event.id))?;
+    let plaintext = serde_json::to_vec(event)?;
+    let mut nonce_bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), plaintext.as_ref())
+        .map_err(|e| anyhow::anyhow!("failed to encrypt event {uid}: {e}"))?;
+    Ok(EncryptedEvent {
+        uid,
+        nonce: base64_encode(&nonce_bytes),
+        ciphertext: base64_encode(&ciphertext),
+    })
+}
+
+fn decrypt_event(cipher: &XChaCha20Poly1305, enc: &EncryptedEvent) -> anyhow::Result<Event> {
+    let nonce_bytes = base64_decode(&enc.nonce)?;
+    let ciphertext = base64_decode(&enc.ciphertext)?;
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce_bytes), ciphertext.as_ref())
+        .map_err(|e| anyhow::anyhow!("failed to decrypt event {}: {e}", enc.uid))?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64_decode(s: &str) -> anyhow::Result<Vec<u8>> {
+    use base64::Engine;
+    Ok(base64::engine::general_purpose::STANDARD.decode(s)?)
+}
+
+/// Push locally-unsynced events to `config.sync_server_url`, then pull and merge any
+/// events pushed by other devices. No-op (with a warning) if sync isn't configured.
+pub async fn run(db: Arc<Db>, config: &Config) -> anyhow::Result<()> {
+    let (Some(server_url), Some(passphrase)) = (&config.sync_server_url, &config.sync_key) else {
+        warn!("Sync is not configured — set sync_server_url and sync_key in config.toml");
+        return Ok(());
+    };
+
+    let cipher = cipher_from_passphrase(passphrase);
+    let device = device_id()?;
+    let client = reqwest::Client::new();
+
+    let unsynced = db.get_unsynced_events(&device, PUSH_BATCH_SIZE)?;
+    info!("Pushing {} unsynced event(s) to {server_url}", unsynced.len());
+    let mut pushed_ids = Vec::new();
+    for event in &unsynced {
+        let enc = encrypt_event(&cipher, event)?;
+        client
+            .post(format!("{server_url}/events"))
+            .json(&enc)
+            .send()
+            .await?
+            .error_for_status()?;
+        pushed_ids.push(event.id);
+    }
+    db.mark_synced(&pushed_ids)?;
+
+    let cursor = db.get_sync_cursor(server_url)?;
+    let resp = client
+        .get(format!("{server_url}/events"))
+        .query(&[("since", cursor.as_deref().unwrap_or(""))])
+        .send()
+        .await?
+        .error_for_status()?;
+    let remote_events: Vec<EncryptedEvent> = resp.json().await?;
+    info!("Pulled {} event(s) from {server_url}", remote_events.len());
+
+    let mut last_uid = cursor;
+    for enc in &remote_events {
+        let event = decrypt_event(&cipher, enc)?;
+        db.upsert_remote_event(&event)?;
+        last_uid = Some(enc.uid.clone());
+    }
+    if let Some(uid) = last_uid {
+        db.set_sync_cursor(server_url, &uid)?;
+    }
+
+    info!("Sync with {server_url} complete");
+    Ok(())
+}
@@ -1,17 +1,28 @@
 //! Core watcher daemon — polls active window and detects idle state via D-Bus.
 
-use chrono::Local;
+use chrono::{Local, NaiveDate, NaiveTime};
+use netstat2::{get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo};
+use regex::Regex;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::process::Command;
 use tokio::signal;
+use tokio::sync::broadcast;
 use tracing::{debug, info, warn};
 use zbus::Connection;
 
-use crate::db::Db;
-
-const POLL_INTERVAL_SECS: u64 = 5;
-const IDLE_THRESHOLD_MS: u64 = 120_000; // 2 minutes
+use crate::bus::{EventKind, SharedCurrent, WatchEvent};
+use crate::config::{self, Config};
+use crate::daterange::TimeRange;
+use crate::db::{Db, MAX_CONNECTIONS_PER_EVENT};
+use crate::hotkey::SharedFocusLabel;
+use crate::notifier;
+use crate::repository::Repository;
 
 #[derive(Debug, Deserialize)]
 struct WindowInfo {
@@ -21,34 +32,97 @@ struct WindowInfo {
 }
 
 pub struct Watcher {
+    /// Local SQLite handle, used directly for rules/hotkey bookkeeping that's always
+    /// local-first regardless of which `Repository` backs the core event store.
     db: Arc<Db>,
+    /// Core event-metrics storage — the local `db` unless `ATRACKER_DATABASE_URL`
+    /// points the whole deployment at a shared Postgres instance instead.
+    repo: Arc<dyn Repository + Send + Sync>,
+    bus: broadcast::Sender<WatchEvent>,
+    current: SharedCurrent,
     current_wm_class: String,
     current_title: String,
     current_pid: i64,
     current_start: chrono::DateTime<Local>,
-    is_idle: bool,
+    /// Live idle flag, behind an atomic so a future status endpoint can read it
+    /// lock-free without going through the DB or broadcast bus.
+    is_idle: Arc<AtomicBool>,
+    poll_interval_secs: Arc<AtomicU64>,
+    idle_threshold_ms: Arc<AtomicU64>,
+    /// Non-atomic config knobs (backend choice, D-Bus service names); re-read whole
+    /// on hot reload since they change far less often than the interval/threshold.
+    config: Config,
+    config_path: PathBuf,
+    config_mtime: Option<SystemTime>,
+    /// Distinct remote endpoints observed for the current window's process, accumulated
+    /// across polls and flushed alongside the event.
+    current_connections: std::collections::BTreeSet<String>,
+    /// Last day each rule fired a notification on, so it only fires once per crossing.
+    rule_last_fired: HashMap<i64, NaiveDate>,
+    /// Label of the hotkey-toggled focus session currently open, if any; stamped onto
+    /// every event flushed while it's set. Shared with [`crate::hotkey`].
+    focus_label: SharedFocusLabel,
 }
 
 impl Watcher {
-    pub fn new(db: Arc<Db>) -> Self {
+    pub fn new(
+        db: Arc<Db>,
+        repo: Arc<dyn Repository + Send + Sync>,
+        bus: broadcast::Sender<WatchEvent>,
+        current: SharedCurrent,
+        focus_label: SharedFocusLabel,
+    ) -> Self {
+        let config_path = config::config_path();
+        let cfg = config::load();
+        let config_mtime = config::mtime(&config_path);
         Self {
             db,
+            repo,
+            bus,
+            current,
             current_wm_class: String::new(),
             current_title: String::new(),
             current_pid: 0,
             current_start: Local::now(),
-            is_idle: false,
+            is_idle: Arc::new(AtomicBool::new(false)),
+            poll_interval_secs: Arc::new(AtomicU64::new(cfg.poll_interval_secs)),
+            idle_threshold_ms: Arc::new(AtomicU64::new(cfg.idle_threshold_ms)),
+            config: cfg,
+            config_path,
+            config_mtime,
+            current_connections: std::collections::BTreeSet::new(),
+            rule_last_fired: HashMap::new(),
+            focus_label,
         }
     }
 
+    /// Publish a live update to the broadcast bus and refresh the shared current-window
+    /// snapshot. Send errors (no subscribers) are ignored.
+    fn publish(&self, kind: EventKind) {
+        let event = WatchEvent {
+            ts: Local::now(),
+            wm_class: self.current_wm_class.clone(),
+            title: self.current_title.clone(),
+            pid: self.current_pid,
+            kind,
+        };
+        if let Ok(mut slot) = self.current.write() {
+            *slot = Some(event.clone());
+        }
+        let _ = self.bus.send(event);
+    }
+
     /// Run the watcher loop until SIGINT/SIGTERM.
     pub async fn run(&mut self) -> anyhow::Result<()> {
         info!(
-            "Watcher started — polling every {POLL_INTERVAL_SECS}s, idle threshold {}s",
-            IDLE_THRESHOLD_MS / 1000
+            "Watcher started — polling every {}s, idle threshold {}s",
+            self.poll_interval_secs.load(Ordering::Relaxed),
+            self.idle_threshold_ms.load(Ordering::Relaxed) / 1000
         );
 
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(POLL_INTERVAL_SECS));
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(
+            self.poll_interval_secs.load(Ordering::Relaxed),
+        ));
 
         loop {
             tokio::select! {
@@ -56,6 +130,17 @@ impl Watcher {
                     if let Err(e) = self.poll().await {
                         warn!("Poll error: {e}");
                     }
+                    if let Some(new_cfg) = config::reload_if_changed(&self.config_path, &mut self.config_mtime) {
+                        let interval_changed = new_cfg.poll_interval_secs != self.poll_interval_secs.load(Ordering::Relaxed);
+                        self.poll_interval_secs.store(new_cfg.poll_interval_secs, Ordering::Relaxed);
+                        self.idle_threshold_ms.store(new_cfg.idle_threshold_ms, Ordering::Relaxed);
+                        self.config = new_cfg;
+                        if interval_changed {
+                            interval = tokio::time::interval(tokio::time::Duration::from_secs(
+                                self.poll_interval_secs.load(Ordering::Relaxed),
+                            ));
+                        }
+                    }
                 }
                 _ = signal::ctrl_c() => {
                     info!("Received shutdown signal");
@@ -68,11 +153,13 @@ impl Watcher {
     }
 
     async fn poll(&mut self) -> anyhow::Result<()> {
-        let idle_ms = get_idle_time().await;
-        let was_idle = self.is_idle;
-        self.is_idle = idle_ms > IDLE_THRESHOLD_MS;
+        let idle_ms = self.get_idle_time().await;
+        let was_idle = self.is_idle.load(Ordering::Relaxed);
+        let idle_threshold_ms = self.idle_threshold_ms.load(Ordering::Relaxed);
+        let now_idle = idle_ms > idle_threshold_ms;
+        self.is_idle.store(now_idle, Ordering::Relaxed);
 
-        if self.is_idle && !was_idle {
+        if now_idle && !was_idle {
             // Just became idle — flush active event, start idle event
             self.flush_current_event();
             self.current_wm_class = "__idle__".to_string();
@@ -80,21 +167,23 @@ impl Watcher {
             self.current_pid = 0;
             self.current_start = Local::now();
             debug!("User went idle");
+            self.publish(EventKind::Idle);
             return Ok(());
         }
 
-        if was_idle && !self.is_idle {
+        if was_idle && !now_idle {
             // Came back from idle — flush idle event
             self.flush_current_event();
             debug!("User returned from idle");
+            self.publish(EventKind::Resume);
         }
 
-        if self.is_idle {
+        if now_idle {
             return Ok(()); // Still idle, nothing to do
         }
 
         // Get active window
-        if let Some(win) = get_active_window().await {
+        if let Some(win) = self.get_active_window().await {
             let wm_class = win.wm_class.unwrap_or_default();
             let title = win.title.unwrap_or_default();
             let pid = win.pid.unwrap_or(0);
@@ -105,13 +194,97 @@ impl Watcher {
                 self.current_title = title.clone();
                 self.current_pid = pid;
                 self.current_start = Local::now();
+                self.current_connections.clear();
                 debug!("Window changed: {wm_class} — {title}");
+                self.publish(EventKind::Active);
+            }
+
+            if pid > 0 {
+                self.current_connections
+                    .extend(get_connections_for_pid(pid));
+                while self.current_connections.len() > MAX_CONNECTIONS_PER_EVENT {
+                    let first = self.current_connections.iter().next().cloned();
+                    if let Some(first) = first {
+                        self.current_connections.remove(&first);
+                    } else {
+                        break;
+                    }
+                }
             }
         }
 
+        self.check_rules();
+
         Ok(())
     }
 
+    /// Compare today's running per-category totals (including the in-progress window)
+    /// against configured rules and fire a debounced desktop notification on crossing.
+    fn check_rules(&mut self) {
+        let rules = match self.db.get_rules() {
+            Ok(rules) => rules,
+            Err(e) => {
+                warn!("Failed to load rules: {e}");
+                return;
+            }
+        };
+        if rules.is_empty() {
+            return;
+        }
+
+        let today = Local::now().date_naive();
+        let summary = match self.repo.get_summary(TimeRange::single_day(today)) {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!("Failed to load today's summary for rule evaluation: {e}");
+                return;
+            }
+        };
+
+        let now_time = Local::now().time();
+
+        for rule in &rules {
+            if self.rule_last_fired.get(&rule.id) == Some(&today) {
+                continue; // already fired today
+            }
+
+            if let (Some(start), Some(end)) = (&rule.window_start, &rule.window_end) {
+                let in_window = match (parse_clock(start), parse_clock(end)) {
+                    (Some(s), Some(e)) => now_time >= s && now_time <= e,
+                    _ => true,
+                };
+                if !in_window {
+                    continue;
+                }
+            }
+
+            let Ok(re) = Regex::new(&format!("(?i){}", rule.category_pattern)) else {
+                continue;
+            };
+            let total: f64 = summary
+                .iter()
+                .filter(|row| re.is_match(&row.wm_class.to_lowercase()))
+                .map(|row| row.total_secs)
+                .sum();
+            // Include the in-progress window if it matches and isn't idle.
+            let total = if !self.is_idle.load(Ordering::Relaxed)
+                && re.is_match(&self.current_wm_class.to_lowercase())
+            {
+                total + (Local::now() - self.current_start).num_milliseconds() as f64 / 1000.0
+            } else {
+                total
+            };
+
+            if total >= rule.daily_threshold_secs {
+                let hours = total / 3600.0;
+                let body = format!("{:.1}h today — {}", hours, rule.message);
+                let summary_line = rule.message.clone();
+                tokio::spawn(async move { notifier::notify(&summary_line, &body).await });
+                self.rule_last_fired.insert(rule.id, today);
+            }
+        }
+    }
+
     fn flush_current_event(&mut self) {
         if self.current_wm_class.is_empty() {
             self.current_start = Local::now();
@@ -126,7 +299,9 @@ impl Watcher {
         }
 
         let is_idle = self.current_wm_class == "__idle__";
-        let result = self.db.insert_event(
+        let connections: Vec<String> = self.current_connections.iter().cloned().collect();
+        let focus_label = self.focus_label.read().unwrap().clone();
+        let result = self.repo.insert_event(
             &self.current_start.format("%Y-%m-%dT%H:%M:%S").to_string(),
             &now.format("%Y-%m-%dT%H:%M:%S").to_string(),
             &self.current_wm_class,
@@ -134,6 +309,8 @@ impl Watcher {
             self.current_pid,
             (duration * 10.0).round() / 10.0,
             is_idle,
+            &connections,
+            focus_label.as_deref(),
         );
 
         if let Err(e) = result {
@@ -148,24 +325,150 @@ impl Watcher {
         }
 
         self.current_start = now;
+        self.current_connections.clear();
+    }
+
+    /// Get active window info, preferring whichever backend the config selects and
+    /// falling back to the other if it yields nothing.
+    async fn get_active_window(&self) -> Option<WindowInfo> {
+        if self.config.window_backend == "xdotool" {
+            match get_active_window_fallback().await {
+                Some(win) => Some(win),
+                None => get_active_window_dbus(&self.config.dbus_window_service).await,
+            }
+        } else {
+            match get_active_window_dbus(&self.config.dbus_window_service).await {
+                Some(win) => Some(win),
+                None => get_active_window_fallback().await,
+            }
+        }
+    }
+
+    /// Get idle time in milliseconds from the configured idle-monitor D-Bus service.
+    async fn get_idle_time(&self) -> u64 {
+        let conn = match Connection::session().await {
+            Ok(c) => c,
+            Err(_) => return 0,
+        };
+
+        let reply = conn
+            .call_method(
+                Some(self.config.dbus_idle_service.as_str()),
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                Some(self.config.dbus_idle_service.as_str()),
+                "GetIdletime",
+                &(),
+            )
+            .await;
+
+        match reply {
+            Ok(msg) => msg.body().deserialize::<u64>().unwrap_or(0),
+            Err(_) => 0,
+        }
     }
 }
 
-/// Get active window info from the GNOME extension via D-Bus.
-async fn get_active_window() -> Option<WindowInfo> {
-    match get_active_window_dbus().await {
-        Some(win) => Some(win),
-        None => get_active_window_fallback().await,
+/// Enumerate distinct remote `host:port` endpoints for TCP sockets owned by `pid` or
+/// any of its descendants (e.g. a browser's content-process children). UDP is skipped:
+/// `netstat2`'s `UdpSocketInfo` only surfaces the local side, since most UDP sockets
+/// (DNS, QUIC before the handshake completes) never `connect()` to a single peer.
+/// Loopback and link-local addresses are skipped as noise. Returns an empty list if
+/// socket enumeration fails or needs privileges this process doesn't have — network
+/// attribution is best-effort and must never block tracking.
+fn get_connections_for_pid(pid: i64) -> Vec<String> {
+    let af_flags = AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6;
+    let proto_flags = ProtocolFlags::TCP;
+    let sockets = match get_sockets_info(af_flags, proto_flags) {
+        Ok(sockets) => sockets,
+        Err(_) => return Vec::new(),
+    };
+
+    let target_pids = descendant_pids(pid as u32);
+    let mut endpoints = std::collections::BTreeSet::new();
+    for socket in sockets {
+        if !socket
+            .associated_pids
+            .iter()
+            .any(|p| target_pids.contains(p))
+        {
+            continue;
+        }
+        let ProtocolSocketInfo::Tcp(tcp) = &socket.protocol_socket_info else {
+            continue;
+        };
+        if is_routable(&tcp.remote_addr) {
+            endpoints.insert(format!("{}:{}", tcp.remote_addr, tcp.remote_port));
+        }
+        if endpoints.len() >= MAX_CONNECTIONS_PER_EVENT {
+            break;
+        }
+    }
+    endpoints.into_iter().collect()
+}
+
+/// `root` plus every pid transitively descended from it, found by walking `/proc`'s
+/// `PPid` links. A process tree is typically tiny, so this just builds the whole
+/// parent map once rather than querying `/proc/<pid>/task/.../children`.
+fn descendant_pids(root: u32) -> std::collections::HashSet<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let pid: u32 = match entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            if let Some(ppid) = read_ppid(pid) {
+                children.entry(ppid).or_default().push(pid);
+            }
+        }
     }
+
+    let mut descendants = std::collections::HashSet::new();
+    let mut stack = vec![root];
+    while let Some(pid) = stack.pop() {
+        if descendants.insert(pid) {
+            if let Some(kids) = children.get(&pid) {
+                stack.extend(kids.iter().copied());
+            }
+        }
+    }
+    descendants
+}
+
+/// Read the parent pid out of `/proc/<pid>/stat`. The comm field (2nd, parenthesized)
+/// may itself contain spaces or parens, so split on the *last* `)` rather than naively
+/// splitting on whitespace.
+fn read_ppid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let after_comm = stat.rsplit_once(')')?.1;
+    after_comm.split_whitespace().nth(1)?.parse().ok()
 }
 
-async fn get_active_window_dbus() -> Option<WindowInfo> {
+/// Skip loopback and link-local addresses — they identify the local machine, not a
+/// remote endpoint worth attributing.
+fn is_routable(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(v4) => !(v4.is_loopback() || v4.is_link_local() || v4.is_unspecified()),
+        IpAddr::V6(v6) => {
+            let link_local = (v6.segments()[0] & 0xffc0) == 0xfe80;
+            !(v6.is_loopback() || link_local || v6.is_unspecified())
+        }
+    }
+}
+
+/// Parse a "HH:MM" clock time used for rule active-window bounds.
+fn parse_clock(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M").ok()
+}
+
+/// Get active window info from the GNOME extension via D-Bus.
+async fn get_active_window_dbus(service: &str) -> Option<WindowInfo> {
     let conn = Connection::session().await.ok()?;
     let reply = conn
         .call_method(
-            Some("org.atracker.WindowTracker"),
+            Some(service),
             "/org/atracker/WindowTracker",
-            Some("org.atracker.WindowTracker"),
+            Some(service),
             "GetActiveWindow",
             &(),
         )
@@ -226,26 +529,3 @@ async fn get_active_window_fallback() -> Option<WindowInfo> {
         pid: Some(0),
     })
 }
-
-/// Get idle time in milliseconds from org.gnome.Mutter.IdleMonitor.
-async fn get_idle_time() -> u64 {
-    let conn = match Connection::session().await {
-        Ok(c) => c,
-        Err(_) => return 0,
-    };
-
-    let reply = conn
-        .call_method(
-            Some("org.gnome.Mutter.IdleMonitor"),
-            "/org/gnome/Mutter/IdleMonitor/Core",
-            Some("org.gnome.Mutter.IdleMonitor"),
-            "GetIdletime",
-            &(),
-        )
-        .await;
-
-    match reply {
-        Ok(msg) => msg.body().deserialize::<u64>().unwrap_or(0),
-        Err(_) => 0,
-    }
-}